@@ -0,0 +1,18 @@
+//! Parses arbitrary bytes as a reply, which must return `Ok` or `Err` without panicking.
+//!
+//! Run with `cargo +nightly fuzz run response_frame` from the root of the repository.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use moteus::registers::CustomRegisters;
+use moteus::ResponseFrame;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ResponseFrame::from_bytes_with(data, &CustomRegisters::default());
+    let mut custom = CustomRegisters::new();
+    custom.add(0x200, "custom");
+    if let Ok(response) = ResponseFrame::from_bytes_with(data, &custom) {
+        let _ = response.to_map();
+    }
+});
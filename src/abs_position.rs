@@ -0,0 +1,86 @@
+//! Reporting the [`AbsPosition`](crate::registers::AbsPosition) relative to a host side zero.
+
+use crate::registers;
+use crate::ResponseFrame;
+
+/// Converts readings of the [`registers::AbsPosition`] into positions relative to a zero kept on the host.
+///
+/// This is a reference frame for display or logging, the controller is not changed. Use
+/// [`crate::Controller::set_output_nearest`] or [`crate::Controller::set_output_exact`] to rezero the
+/// controller itself. Positions are in revolutions, and the relative position is the plain difference from
+/// the zero, without wrapping.
+///
+/// ```rust
+/// # use moteus::AbsPositionRef;
+/// let mut reference = AbsPositionRef::default();
+/// reference.set_zero(0.25);
+/// assert_eq!(reference.relative(0.75), 0.5);
+/// assert_eq!(reference.relative(0.0), -0.25);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AbsPositionRef {
+    zero: f32,
+}
+
+impl AbsPositionRef {
+    /// Creates an [`AbsPositionRef`] whose zero is at the raw position `zero`
+    pub fn new(zero: f32) -> Self {
+        Self { zero }
+    }
+
+    /// The raw position which is reported as zero
+    pub fn zero(&self) -> f32 {
+        self.zero
+    }
+
+    /// Sets the raw position which is reported as zero
+    pub fn set_zero(&mut self, zero: f32) {
+        self.zero = zero;
+    }
+
+    /// Captures the [`registers::AbsPosition`] of a response as the new zero, returning it.
+    /// If the response does not include the register [`None`] is returned and the zero is unchanged.
+    pub fn capture(&mut self, response: &ResponseFrame) -> Option<f32> {
+        let zero = response.get::<registers::AbsPosition>()?.value();
+        self.zero = zero;
+        Some(zero)
+    }
+
+    /// Returns the raw position `raw` relative to the zero
+    pub fn relative(&self, raw: f32) -> f32 {
+        raw - self.zero
+    }
+
+    /// Returns the [`registers::AbsPosition`] of a response relative to the zero,
+    /// or [`None`] if the response does not include it
+    pub fn relative_from(&self, response: &ResponseFrame) -> Option<f32> {
+        response
+            .get::<registers::AbsPosition>()
+            .map(|position| self.relative(position.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        // AbsPosition (Float)
+        let response = |position: f32| {
+            ResponseFrame::from_bytes(&[&[0x2d, 0x06][..], &position.to_le_bytes()].concat())
+                .unwrap()
+        };
+        let mut reference = AbsPositionRef::new(0.5);
+        assert_eq!(reference.relative_from(&response(0.75)), Some(0.25));
+
+        assert_eq!(reference.capture(&response(0.125)), Some(0.125));
+        assert_eq!(reference.relative_from(&response(0.625)), Some(0.5));
+
+        let empty = ResponseFrame::from_bytes(&[]).unwrap();
+        assert_eq!(reference.capture(&empty), None);
+        assert_eq!(reference.zero(), 0.125);
+        assert_eq!(reference.relative_from(&empty), None);
+    }
+}
@@ -0,0 +1,124 @@
+//! Reading a fixed set of registers into a struct, see [`register_block!`](crate::register_block).
+
+use crate::{FrameBuilder, ResponseFrame};
+
+/// A struct of registers that is read in a single query. Implemented with [`register_block!`](crate::register_block).
+pub trait RegisterBlock: Sized {
+    /// Returns a frame reading every register of the block
+    fn query() -> FrameBuilder;
+    /// Parses the registers of the block from a response.
+    /// If any of the registers is not found in the response frame [`None`] is returned.
+    fn from_response(response: &ResponseFrame) -> Option<Self>;
+}
+
+/// Defines a struct with a field per register, implementing [`RegisterBlock`] to read all of them in one query.
+///
+/// Every register is read with the resolution given after the struct name. Registers with contiguous
+/// addresses, such as the command registers from `0x020` to `0x027`, are read in a single subframe,
+/// the most compact frame possible for the set.
+/// Each field holds the value of its register, `<R as Register>::INNER`.
+///
+/// ```rust,no_run
+/// # use moteus::{register_block, RegisterBlock, Resolution};
+/// # use moteus::frame::QueryType;
+/// # use moteus::registers::*;
+/// register_block! {
+///     /// The position command of a controller
+///     #[derive(Debug)]
+///     pub struct PositionCommand(Resolution::Float) {
+///         /// The commanded position
+///         pub position: CommandPosition,
+///         /// The commanded velocity
+///         pub velocity: CommandVelocity,
+///         /// The commanded feedforward torque
+///         pub torque: CommandFeedforwardTorque,
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+/// let response = c.query(1, QueryType::Custom(PositionCommand::query()))?;
+/// let command = PositionCommand::from_response(&response);
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! register_block {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($resolution:expr) {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $register:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: <$register as $crate::registers::Register>::INNER,
+            )*
+        }
+
+        impl $crate::RegisterBlock for $name {
+            fn query() -> $crate::FrameBuilder {
+                let mut builder = $crate::Frame::builder();
+                $(
+                    builder.add(
+                        <$register as $crate::registers::Readable>::read_with_resolution($resolution),
+                    );
+                )*
+                builder
+            }
+
+            fn from_response(response: &$crate::ResponseFrame) -> Option<Self> {
+                Some(Self {
+                    $($field: response.get::<$register>()?.value(),)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::registers::*;
+    use crate::Resolution;
+
+    register_block! {
+        #[derive(Debug, PartialEq)]
+        struct Command(Resolution::Int16) {
+            position: CommandPosition,
+            velocity: CommandVelocity,
+            feedforward_torque: CommandFeedforwardTorque,
+            kp_scale: CommandKpScale,
+            kd_scale: CommandKdScale,
+            max_torque: CommandPositionMaxTorque,
+            stop_position: CommandStopPosition,
+            timeout: CommandTimeout,
+        }
+    }
+
+    #[test]
+    fn test_register_block() {
+        // 8 registers from 0x020 in a single ReadInt16 subframe
+        let frame = Command::query().build();
+        assert_eq!(frame.as_bytes().unwrap(), vec![0x14, 0x08, 0x20]);
+
+        let mut reply = vec![0x24, 0x08, 0x20];
+        reply.extend([0u8; 16]);
+        let response = ResponseFrame::from_bytes(&reply).unwrap();
+        let command = Command::from_response(&response).unwrap();
+        assert_eq!(command.position, 0.0);
+        assert_eq!(command.timeout, 0.0);
+
+        // CommandTimeout missing
+        let mut reply = vec![0x24, 0x07, 0x20];
+        reply.extend([0u8; 14]);
+        let response = ResponseFrame::from_bytes(&reply).unwrap();
+        assert_eq!(Command::from_response(&response), None);
+    }
+}
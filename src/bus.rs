@@ -1,13 +1,48 @@
+use crate::driver::DriverFaultReport;
+use crate::encoder::{EncoderDiagnostics, EncoderReadings};
 use crate::error::Error;
-use crate::frame::QueryType;
-use crate::protocol::{Frame, FrameBuilder, ResponseFrame};
-use crate::FrameParseError;
+use crate::frame::{Position, QueryType, Stop};
+use crate::latency::LatencyStats;
+use crate::protocol::diagnostic::{self, DIAGNOSTIC_CHANNEL, MAX_DIAGNOSTIC_CHUNK};
+use crate::protocol::{
+    BusTiming, Frame, FrameBuilder, Resolution, ResolutionPolicy, ResponseFrame,
+};
+use crate::registers;
+use crate::registers::{CustomRegisters, Faults, Modes, ReadSet, Readable, Res, Writeable};
+use crate::throttle::{Throttle, ThrottlePolicy};
+use crate::watchdog::WatchdogGuard;
 use fdcanusb::CanFdFrame;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 /// The main struct for interacting with the Moteus.
+///
+/// Frames are sent with the 11-bit form of the moteus arbitration id: the destination id in the low byte,
+/// and bit 15 set when a reply is requested. Controllers configured with a non-zero `id.can_prefix`
+/// expect the prefix in bits 16-28 of a 29-bit extended id, which can't be represented by the 16-bit
+/// [`CanFdFrame::arbitration_id`], so those controllers are not currently supported.
+///
+/// Replies are received in the same call that sends the query, so each transfer waits for its reply.
+/// Receiving on a background thread would need the transport to be split into independent send and receive
+/// halves, which the fdcanusb can't provide as each frame sent is acknowledged on the same serial stream as
+/// the replies. Use [`Controller::send_no_response`] to command without waiting.
 pub struct Controller<T> {
     transport: T,
     default_query: FrameBuilder,
+    custom_registers: CustomRegisters,
+    latency_stats: Option<LatencyStats>,
+    fault_guard: Option<HashMap<u8, Faults>>,
+    max_torque: HashMap<u8, f32>,
+    /// The last [`registers::MillisecondCounter`] per id, and whether it had not advanced
+    stale_check: Option<HashMap<u8, (i32, bool)>>,
+    /// Whether [`Controller::set_stale_check`] added the [`registers::MillisecondCounter`] to the default query
+    stale_counter_added: bool,
+    bus_timing: Option<BusTiming>,
+    last_commands: HashMap<u8, FrameBuilder>,
+    max_payload: Option<usize>,
+    resolution_policy: Option<ResolutionPolicy>,
+    watchdog_guard: Option<WatchdogGuard>,
+    throttle: Option<Throttle>,
     /// Disable BRS (Bit Rate Switch) in the CAN FD frames. Useful if your CAN network is unable to perform.
     pub disable_brs: bool,
 }
@@ -30,18 +65,17 @@ impl Controller<fdcanusb::FdCanUSB<fdcanusb::serial2::SerialPort>> {
         serial_settings: impl fdcanusb::serial2::IntoSettings,
         disable_brs: bool,
     ) -> Result<Self, std::io::Error> {
-        Ok(Self {
-            transport: fdcanusb::FdCanUSB::open(path, serial_settings)?,
-            default_query: crate::frame::Query::default().into(),
+        Ok(Self::new(
+            fdcanusb::FdCanUSB::open(path, serial_settings)?,
             disable_brs,
-        })
+        ))
     }
 }
 
 impl<T, F> Controller<T>
 where
     T: crate::transport::Transport<Frame = F>,
-    F: From<CanFdFrame> + TryInto<ResponseFrame, Error = FrameParseError>,
+    F: From<CanFdFrame> + Into<CanFdFrame>,
 {
     /// Create a new [`Controller`] instance with a given transport.
     ///
@@ -54,11 +88,7 @@ where
     /// # }
     /// ```
     pub fn new(transport: T, disable_brs: bool) -> Self {
-        Self {
-            transport,
-            default_query: crate::frame::Query::default().into(),
-            disable_brs,
-        }
+        Self::with_query(transport, disable_brs, crate::frame::Query::default())
     }
     /// Creates a new [`Controller`] instance with a custom default query.
     ///
@@ -89,10 +119,223 @@ where
         Controller {
             transport,
             default_query: default_query.into(),
+            custom_registers: CustomRegisters::default(),
+            latency_stats: None,
+            fault_guard: None,
+            max_torque: HashMap::new(),
+            stale_check: None,
+            stale_counter_added: false,
+            bus_timing: None,
+            last_commands: HashMap::new(),
+            max_payload: None,
+            resolution_policy: None,
+            watchdog_guard: None,
+            throttle: None,
             disable_brs,
         }
     }
 
+    /// Returns the default query, merged into frames sent with [`QueryType::Default`] and [`QueryType::DefaultAnd`].
+    ///
+    /// The [`ResolutionPolicy`] is applied when a frame is sent, so it is not reflected here.
+    pub fn default_query(&self) -> &FrameBuilder {
+        &self.default_query
+    }
+
+    /// Sets the [`CustomRegisters`] used when parsing responses.
+    ///
+    /// Registers in the table are returned as raw data, see [`ResponseFrame::get_custom`].
+    pub fn set_custom_registers(&mut self, custom_registers: CustomRegisters) {
+        self.custom_registers = custom_registers;
+    }
+
+    /// Starts recording the round trip latency of every transfer that expects a response.
+    /// The last `window` samples are kept. Any previously recorded samples are discarded.
+    pub fn enable_latency_stats(&mut self, window: usize) {
+        self.latency_stats = Some(LatencyStats::new(window));
+    }
+
+    /// Stops recording latency and returns the recorded [`LatencyStats`], if enabled.
+    pub fn disable_latency_stats(&mut self) -> Option<LatencyStats> {
+        self.latency_stats.take()
+    }
+
+    /// Returns the recorded [`LatencyStats`], or [`None`] if they are not enabled.
+    pub fn latency_stats(&self) -> Option<&LatencyStats> {
+        self.latency_stats.as_ref()
+    }
+
+    /// Sets the [`BusTiming`] of the CAN-FD bus, used by [`Controller::estimated_tx_time`].
+    pub fn set_bus_timing(&mut self, timing: Option<BusTiming>) {
+        self.bus_timing = timing;
+    }
+
+    /// Returns the [`BusTiming`] of the CAN-FD bus, if set.
+    pub fn bus_timing(&self) -> Option<BusTiming> {
+        self.bus_timing
+    }
+
+    /// Estimates the time taken to transmit `frame` on the bus, taking [`Controller::disable_brs`] into account.
+    /// Returns [`None`] if the [`BusTiming`] has not been set.
+    pub fn estimated_tx_time(&self, frame: &Frame) -> Option<Duration> {
+        self.bus_timing
+            .map(|timing| frame.tx_time(&timing, !self.disable_brs))
+    }
+
+    /// Sets a [`ResolutionPolicy`], applied to the read registers of every frame sent
+    /// (including the default query). The policy overrides the resolution requested for each register.
+    pub fn set_resolution_policy(&mut self, policy: Option<ResolutionPolicy>) {
+        self.resolution_policy = policy;
+    }
+
+    /// Returns the [`ResolutionPolicy`], if set.
+    pub fn resolution_policy(&self) -> Option<&ResolutionPolicy> {
+        self.resolution_policy.as_ref()
+    }
+
+    /// Sets a [`WatchdogGuard`], which records the time of every command sent.
+    /// A [`Stop`] clears the guard for the controller, as the watchdog only applies while it is active.
+    pub fn set_watchdog_guard(&mut self, guard: Option<WatchdogGuard>) {
+        self.watchdog_guard = guard;
+    }
+
+    /// Returns the [`WatchdogGuard`], if set, to check how long remains before the watchdog of a controller trips.
+    pub fn watchdog_guard(&self) -> Option<&WatchdogGuard> {
+        self.watchdog_guard.as_ref()
+    }
+
+    /// Sets a [`Throttle`], enforcing a minimum interval between the commands sent to each controller.
+    /// Commands sent too soon are handled according to its [`ThrottlePolicy`].
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.throttle = throttle;
+    }
+
+    /// Returns the [`Throttle`], if set.
+    pub fn throttle(&self) -> Option<&Throttle> {
+        self.throttle.as_ref()
+    }
+
+    /// Sends the commands held by a [`Throttle`] with [`ThrottlePolicy::Coalesce`] whose interval has passed,
+    /// returning the ids they were sent to. Pending commands are sent without a query, and no response is read.
+    ///
+    /// Call this regularly, such as once per loop, so the latest command reaches each controller.
+    pub fn flush_throttled(&mut self) -> Result<Vec<u8>, Error<T::Error>> {
+        let Some(throttle) = &self.throttle else {
+            return Ok(Vec::new());
+        };
+        let now = Instant::now();
+        let mut ready: Vec<u8> = throttle
+            .pending()
+            .filter(|id| throttle.remaining_at(*id, now).is_none())
+            .collect();
+        ready.sort_unstable();
+        for &id in &ready {
+            let command = self
+                .throttle
+                .as_ref()
+                .and_then(|t| t.pending_command(id))
+                .cloned();
+            if let Some(command) = command {
+                self.transfer_single_no_response(id, command)?;
+            }
+        }
+        Ok(ready)
+    }
+
+    /// Sets a maximum payload size in bytes, below the 64 byte limit of CAN-FD.
+    ///
+    /// Frames larger than the limit are refused with [`Error::PayloadTooLarge`] instead of being sent.
+    /// Diagnostic stream frames are not checked.
+    pub fn set_max_payload(&mut self, max_payload: Option<usize>) {
+        self.max_payload = max_payload;
+    }
+
+    /// Returns the maximum payload size, if set.
+    pub fn max_payload(&self) -> Option<usize> {
+        self.max_payload
+    }
+
+    /// Enables or disables the fault guard.
+    ///
+    /// While enabled, the [`registers::Fault`] returned in each [`ResponseFrame`] is remembered per id.
+    /// Once a controller has reported a fault, frames that set the [`registers::Mode`] to anything other than
+    /// [`Modes::Stopped`] are refused with [`Error::Faulted`] until a [`crate::frame::Stop`] is sent
+    /// (see [`Controller::clear_fault`]) or a response reports [`Faults::Success`].
+    /// Only faults observed while the guard is enabled are tracked.
+    pub fn set_fault_guard(&mut self, enabled: bool) {
+        match (enabled, &self.fault_guard) {
+            (true, None) => self.fault_guard = Some(HashMap::new()),
+            (false, _) => self.fault_guard = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    /// Sets a torque limit in Nm for `id`, or removes it with [`None`].
+    ///
+    /// While set, every frame sent to `id` that sets the [`registers::Mode`] to [`Modes::Position`] writes
+    /// [`registers::CommandPositionMaxTorque`], lowering the maximum torque of the command to the limit
+    /// if it is not set, or set higher.
+    pub fn set_max_torque(&mut self, id: u8, max_torque: Option<f32>) {
+        match max_torque {
+            Some(max_torque) => {
+                let _ = self.max_torque.insert(id, max_torque);
+            }
+            None => {
+                let _ = self.max_torque.remove(&id);
+            }
+        }
+    }
+
+    /// Returns the torque limit in Nm for `id`, if set.
+    pub fn max_torque(&self, id: u8) -> Option<f32> {
+        self.max_torque.get(&id).copied()
+    }
+
+    /// Enables or disables the stale response check.
+    ///
+    /// While enabled, the [`registers::MillisecondCounter`] returned in each [`ResponseFrame`] is remembered per id.
+    /// A response whose counter has not advanced since the previous response is likely a buffered reply
+    /// rather than fresh data, so a warning is logged and [`Controller::is_stale`] returns `true` for that id.
+    /// Enabling the check adds [`registers::MillisecondCounter`] to the default query, and disabling it removes
+    /// the register again unless it was already in the default query.
+    pub fn set_stale_check(&mut self, enabled: bool) {
+        match (enabled, &self.stale_check) {
+            (true, None) => {
+                self.stale_check = Some(HashMap::new());
+                self.stale_counter_added = !self
+                    .default_query
+                    .registers()
+                    .any(|reg| reg.address == registers::RegisterAddr::MillisecondCounter);
+                if self.stale_counter_added {
+                    self.default_query
+                        .add(registers::MillisecondCounter::read());
+                }
+            }
+            (false, Some(_)) => {
+                self.stale_check = None;
+                if std::mem::take(&mut self.stale_counter_added) {
+                    self.default_query
+                        .remove(registers::RegisterAddr::MillisecondCounter);
+                }
+            }
+            (true, Some(_)) | (false, None) => {}
+        }
+    }
+
+    /// Returns `true` if the last response from `id` had the same [`registers::MillisecondCounter`]
+    /// as the one before it. Always `false` unless enabled with [`Controller::set_stale_check`].
+    pub fn is_stale(&self, id: u8) -> bool {
+        self.stale_check
+            .as_ref()
+            .and_then(|counters| counters.get(&id))
+            .is_some_and(|(_, stale)| *stale)
+    }
+
+    /// Sends a [`crate::frame::Stop`] to clear a fault on the controller, which also clears the fault guard for `id`.
+    pub fn clear_fault(&mut self, id: u8) -> Result<(), Error<T::Error>> {
+        self.send_no_response(id, Stop)
+    }
+
     /// Sends a single query frame to the moteus and returns a [`ResponseFrame`].
     ///
     /// The query frame can be set with [`QueryType`].
@@ -101,13 +344,32 @@ where
     /// Use [`QueryType::Custom`] to use a custom query frame (without the default).
     pub fn query(&mut self, id: u8, query: QueryType) -> Result<ResponseFrame, Error<T::Error>> {
         let frame = match query {
-            QueryType::Default => self.default_query.clone().build(),
-            QueryType::DefaultAnd(q_frame) => self.default_query.clone().merge(q_frame).build(),
-            QueryType::Custom(q_frame) => q_frame.build(),
+            QueryType::Default => self.default_query.clone(),
+            QueryType::DefaultAnd(q_frame) => self.default_query.clone().merge(q_frame),
+            QueryType::Custom(q_frame) => q_frame,
         };
         self.transfer_single_with_response(id, frame)
     }
 
+    /// Sends the default query frame with reads of the extra registers `S` added, and returns a [`ResponseFrame`].
+    ///
+    /// `S` is a single [`Readable`] register or a tuple of them, each read with its default resolution.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::registers::{MotorTemperature, QCurrent, DCurrent};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// let response = c.query_with::<MotorTemperature>(1)?;
+    /// let response = c.query_with::<(QCurrent, DCurrent)>(1)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_with<S: ReadSet>(&mut self, id: u8) -> Result<ResponseFrame, Error<T::Error>> {
+        let mut extra = Frame::builder();
+        S::add_reads(&mut extra);
+        self.query(id, QueryType::DefaultAnd(extra))
+    }
+
     /// Send a single frame to the moteus. No response will be returned.
     /// Use [`Controller::send_with_query`] to get a response.
     pub fn send_no_response(
@@ -115,8 +377,35 @@ where
         id: u8,
         frame: impl Into<FrameBuilder>,
     ) -> Result<(), Error<T::Error>> {
-        let frame = frame.into().build();
-        self.transfer_single_no_response(id, frame)
+        self.transfer_single_no_response(id, frame.into())
+    }
+
+    /// Sends a single frame to the moteus requesting a reply, to confirm that it was received.
+    ///
+    /// No query is merged into the frame, so the controller replies with an empty frame.
+    /// Any valid reply from `id` is taken as acknowledgement and returned. If no reply is received
+    /// within the timeout of the transport, or the reply is from another controller, [`Error::NoResponse`] is returned.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::frame::Stop;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// c.send_confirmed(1, Stop)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_confirmed(
+        &mut self,
+        id: u8,
+        frame: impl Into<FrameBuilder>,
+    ) -> Result<ResponseFrame, Error<T::Error>> {
+        let response = self.transfer_single_raw(id, frame.into())?;
+        if ((response.arbitration_id >> 8) & 0x7f) as u8 != id {
+            return Err(Error::NoResponse);
+        }
+        let response = ResponseFrame::from_can_frame(&response, &self.custom_registers)?;
+        self.observe(id, &response);
+        Ok(response)
     }
 
     /// Sends a single frame with a query to the moteus and returns a [`ResponseFrame`].
@@ -131,49 +420,2150 @@ where
         frame: impl Into<FrameBuilder>,
         query: QueryType,
     ) -> Result<ResponseFrame, Error<T::Error>> {
-        let frame = match query {
-            QueryType::Default => frame.into().merge(self.default_query.clone()).build(),
-            QueryType::DefaultAnd(q_frame) => frame
-                .into()
-                .merge(self.default_query.clone())
-                .merge(q_frame)
-                .build(),
-            QueryType::Custom(q_frame) => frame.into().merge(q_frame).build(),
-        };
+        let frame = self.merge_query(frame.into(), query);
         self.transfer_single_with_response(id, frame)
     }
 
-    fn transfer_single_no_response(
+    /// Sends a single frame with a query to the moteus, as with [`Controller::send_with_query`],
+    /// and returns the [`ResponseFrame`] together with the raw [`CanFdFrame`] it was parsed from.
+    ///
+    /// Useful for debugging, to compare the decoded registers with the bytes received.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::frame::{Position, QueryType};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// let (response, raw) = c.send_with_query_raw(1, Position::hold(), QueryType::Default)?;
+    /// println!("{:02x?} decoded as {:?}", raw.data, response.to_map());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_with_query_raw(
+        &mut self,
+        id: u8,
+        frame: impl Into<FrameBuilder>,
+        query: QueryType,
+    ) -> Result<(ResponseFrame, CanFdFrame), Error<T::Error>> {
+        let frame = self.merge_query(frame.into(), query);
+        let raw = self.transfer_single_raw(id, frame)?;
+        let response = ResponseFrame::from_can_frame(&raw, &self.custom_registers)?;
+        self.observe(id, &response);
+        Ok((response, raw))
+    }
+
+    /// Returns the [`CanFdFrame`] that would be sent to `id` for `frame`, without sending it.
+    ///
+    /// The frame goes through the same checks and changes as a sent frame, such as the fault guard,
+    /// the resolution policy and the torque limit, but it is not remembered as the last command.
+    /// Merge a query into `frame` before calling this, as with [`Controller::send_with_query`],
+    /// and set `expect_reply` if the controller should reply.
+    pub fn prepare_frame(
+        &mut self,
+        id: u8,
+        frame: impl Into<FrameBuilder>,
+        expect_reply: bool,
+    ) -> Result<CanFdFrame, Error<T::Error>> {
+        let frame = self.shape(id, frame.into())?;
+        let data = self.serialize(frame)?;
+        Ok(self.can_frame(id, data, expect_reply))
+    }
+
+    /// Sends the same frame with a query to each controller in `ids` and returns the result for each id, in order.
+    ///
+    /// The frame is merged with the query and serialized once, then transmitted to every id.
+    /// It is only serialized again for an id whose frame is changed by a per-controller setting,
+    /// such as [`Controller::set_max_torque`]. Every id in `ids` has an entry in the result, so a controller
+    /// which does not reply has the error of the transport, such as [`Error::NoResponse`], and the
+    /// following ids are still sent to.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::frame::{Position, QueryType};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// for (id, response) in c.send_same_to(&[1, 2, 3], Position::hold(), QueryType::Default) {
+    ///     if let Err(e) = response {
+    ///         println!("{id} did not reply: {e}");
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_same_to(
+        &mut self,
+        ids: &[u8],
+        frame: impl Into<FrameBuilder>,
+        query: QueryType,
+    ) -> Vec<IdResponse<T::Error>> {
+        let frame = self.merge_query(frame.into(), query);
+        let mut built: Option<(FrameBuilder, Vec<u8>)> = None;
+        ids.iter()
+            .map(|&id| (id, self.send_shared(id, &frame, &mut built)))
+            .collect()
+    }
+
+    /// Sends one query to the broadcast id and collects the replies of every controller that answers.
+    ///
+    /// Replies are read until `ids_expected` have been received, `timeout` has elapsed or the transport
+    /// returns [`Error::NoResponse`] or [`Error::Timeout`], such as when its read timeout expires. Any other
+    /// error of the transport is returned. Each reply is keyed by the source id in its arbitration id,
+    /// in the order they were received, with the error for a reply that failed to parse.
+    /// Controllers that do not reply are missing from the result, so compare its length against `ids_expected`.
+    pub fn query_all(
+        &mut self,
+        ids_expected: usize,
+        query: QueryType,
+        timeout: Duration,
+    ) -> Result<Vec<IdResponse<T::Error>>, Error<T::Error>> {
+        let frame = self.merge_query(Frame::builder(), query);
+        let (data, command) = self.prepare(BROADCAST_ID, frame)?;
+        self.transmit_command(BROADCAST_ID, data, command, true)?;
+        let start = Instant::now();
+        let mut responses = Vec::with_capacity(ids_expected);
+        while responses.len() < ids_expected && start.elapsed() < timeout {
+            let frame: CanFdFrame = match self.transport.receive() {
+                Ok(frame) => frame.into(),
+                Err(Error::NoResponse | Error::Timeout) => break,
+                Err(e) => return Err(e),
+            };
+            let source = ((frame.arbitration_id >> 8) & 0x7f) as u8;
+            let response =
+                ResponseFrame::from_can_frame(&frame, &self.custom_registers).map_err(Error::from);
+            if let Ok(response) = &response {
+                self.observe(source, response);
+            }
+            responses.push((source, response));
+        }
+        Ok(responses)
+    }
+
+    /// Sends a single frame with a query to the moteus and reads the reply without parsing it.
+    ///
+    /// This keeps the bus in sync when the reply is not needed, such as when resetting the watchdog,
+    /// without the cost of decoding a [`ResponseFrame`]. As the reply is not parsed, it is not observed by
+    /// the fault guard or the stale check.
+    pub fn send_with_query_ignore(
         &mut self,
         id: u8,
-        frame: impl Into<Frame>,
+        frame: impl Into<FrameBuilder>,
+        query: QueryType,
     ) -> Result<(), Error<T::Error>> {
-        let frame = frame.into();
-        let arbitration_id = id as u16;
-        let frame = CanFdFrame {
-            arbitration_id,
-            data: frame.as_bytes()?,
-            brs: Some(!self.disable_brs),
-            ..Default::default()
-        };
-        self.transport.transmit(frame.into())?;
+        let frame = self.merge_query(frame.into(), query);
+        let _ = self.transfer_single_raw(id, frame)?;
         Ok(())
     }
-    fn transfer_single_with_response(
+
+    /// Reads a single register `R` from the moteus, with the default resolution of the register.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::registers::Position;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// let position = c.read::<Position>(1)?.value();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read<R: Readable>(&mut self, id: u8) -> Result<Res<R>, Error<T::Error>> {
+        let mut frame = Frame::builder();
+        frame.add(R::read());
+        self.query(id, QueryType::Custom(frame))?
+            .get::<R>()
+            .ok_or(Error::MissingRegister(R::address()))
+    }
+
+    /// Writes `value` to a single register `R` of the moteus, with the default resolution of the register.
+    /// No response will be returned.
+    pub fn write<R: Writeable>(&mut self, id: u8, value: R::INNER) -> Result<(), Error<T::Error>> {
+        let mut frame = Frame::builder();
+        frame.add(R::write(value)?);
+        self.transfer_single_no_response(id, frame)
+    }
+
+    /// Sets the output position of the moteus to the position nearest the current one which is
+    /// equivalent to `position`, modulo one revolution, with [`registers::SetOutputNearest`].
+    ///
+    /// Use this for outputs that wrap, where the number of revolutions does not matter.
+    /// No response will be returned.
+    pub fn set_output_nearest(&mut self, id: u8, position: f32) -> Result<(), Error<T::Error>> {
+        self.write::<registers::SetOutputNearest>(id, position)
+    }
+
+    /// Sets the output position of the moteus to exactly `position`, with [`registers::SetOutputExact`].
+    ///
+    /// Use this for geared or limited outputs, where the number of revolutions of the output is meaningful.
+    /// No response will be returned.
+    pub fn set_output_exact(&mut self, id: u8, position: f32) -> Result<(), Error<T::Error>> {
+        self.write::<registers::SetOutputExact>(id, position)
+    }
+
+    /// Writes `value` to the register `R` of the moteus and reads it back in the same frame, returning whether
+    /// the controller holds the written value.
+    ///
+    /// The values are compared after quantizing both to the default resolution of the register, so a value
+    /// read back within the quantization step of an integer resolution is considered equal.
+    pub fn write_and_verify<R: Writeable + Readable>(
+        &mut self,
+        id: u8,
+        value: R::INNER,
+    ) -> Result<bool, Error<T::Error>> {
+        let written = registers::RegisterData::from(R::write(value)?);
+        let mut query = Frame::builder();
+        query.add(R::read());
+        let mut frame = Frame::builder();
+        frame.add(written.clone());
+        // writes are sent before reads, so the reply holds the new value
+        let read = self
+            .send_with_query(id, frame, QueryType::Custom(query))?
+            .get::<R>()
+            .ok_or(Error::MissingRegister(R::address()))?;
+        Ok(R::write_with_resolution(read.value, written.resolution)
+            .is_ok_and(|read| registers::RegisterData::from(read).data == written.data))
+    }
+
+    /// Reads the single register `R` from each controller in `ids`, with the default resolution of the register.
+    ///
+    /// Returns the register of each controller, or [`None`] if it did not respond, the reply could not be parsed
+    /// or the register was missing from it.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::registers::Position;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// for (id, position) in c.read_all::<Position>(&[1, 2, 3]) {
+    ///     println!("{id}: {:?}", position.map(|p| p.value()));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_all<R: Readable>(&mut self, ids: &[u8]) -> Vec<(u8, Option<Res<R>>)> {
+        ids.iter()
+            .map(|&id| (id, self.read::<R>(id).ok()))
+            .collect()
+    }
+
+    /// Queries the [`registers::Mode`] and [`registers::Fault`] of each controller in `ids`.
+    ///
+    /// Returns the fault reported by each controller, [`Faults::Success`] if it is not faulted,
+    /// or [`None`] if it did not respond or the reply could not be parsed.
+    /// How long to wait for a missing controller is determined by the read timeout of the transport,
+    /// so a short timeout keeps the scan quick.
+    pub fn fault_report(&mut self, ids: &[u8]) -> Vec<(u8, Option<Faults>)> {
+        ids.iter()
+            .map(|&id| {
+                let fault = self
+                    .query(id, QueryType::Custom(mode_and_fault()))
+                    .ok()
+                    .and_then(|response| response.get::<registers::Fault>())
+                    .map(|fault| fault.value());
+                (id, fault)
+            })
+            .collect()
+    }
+
+    /// Reads the [`registers::Fault`], [`registers::DriverFault1`] and [`registers::DriverFault2`] of each
+    /// controller in `ids`, returning a [`DriverFaultReport`] for each controller that is faulted.
+    ///
+    /// Controllers reporting [`Faults::Success`] are left out, and those that did not respond or whose reply
+    /// could not be parsed are returned with [`None`]. Run it after a [`Faults::MotorDriverFault`] to find which
+    /// gate driver condition tripped on which controller.
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// for (id, report) in c.driver_fault_report(&[1, 2, 3]) {
+    ///     match report {
+    ///         Some(report) => println!("{id}: {report}"),
+    ///         None => println!("{id}: no response"),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn driver_fault_report(&mut self, ids: &[u8]) -> Vec<(u8, Option<DriverFaultReport>)> {
+        ids.iter()
+            .map(|&id| {
+                let report = self
+                    .query(id, QueryType::Custom(DriverFaultReport::query()))
+                    .ok()
+                    .and_then(|response| DriverFaultReport::from_response(&response));
+                (id, report)
+            })
+            .filter(|(_, report)| !matches!(report, Some(r) if r.fault == Faults::Success))
+            .collect()
+    }
+
+    /// Returns whether the controller is ready to be commanded, reading its [`registers::Mode`],
+    /// [`registers::Fault`] and [`registers::EncoderValidity`] in one query.
+    ///
+    /// The controller is ready when it reports [`Faults::Success`], encoder 0 (the encoder used for commutation)
+    /// is valid, and it is not in [`Modes::Fault`] or [`Modes::PositionTimeout`], which need a [`Stop`] first.
+    /// [`Modes::Enabling`], [`Modes::Calibrating`] and [`Modes::CalibrationComplete`] are the startup sequence
+    /// of the controller, so they are reported as not ready yet rather than as a fault.
+    pub fn is_ready(&mut self, id: u8) -> Result<bool, Error<T::Error>> {
+        let mut query = mode_and_fault();
+        query.add(registers::EncoderValidity::read());
+        let response = self.query(id, QueryType::Custom(query))?;
+        let mode = response
+            .get::<registers::Mode>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Mode))?
+            .value();
+        let fault = response
+            .get::<registers::Fault>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Fault))?
+            .value();
+        let validity = response
+            .get::<registers::EncoderValidity>()
+            .ok_or(Error::MissingRegister(
+                registers::RegisterAddr::EncoderValidity,
+            ))?
+            .value() as u8;
+        let startup = matches!(
+            mode,
+            Modes::Enabling | Modes::Calibrating | Modes::CalibrationComplete
+        );
+        let needs_stop = matches!(mode, Modes::Fault | Modes::PositionTimeout);
+        Ok(fault == Faults::Success && validity & 1 != 0 && !startup && !needs_stop)
+    }
+
+    /// Polls the [`registers::Mode`] of the moteus until it reports `mode`.
+    ///
+    /// Returns [`Error::Faulted`] if the controller enters [`Modes::Fault`] instead,
+    /// or [`Error::Timeout`] if `mode` is not reached within `timeout`.
+    pub fn wait_for_mode(
+        &mut self,
+        id: u8,
+        mode: Modes,
+        timeout: Duration,
+    ) -> Result<(), Error<T::Error>> {
+        let start = Instant::now();
+        loop {
+            let response = self.query(id, QueryType::Custom(mode_and_fault()))?;
+            let current = response
+                .get::<registers::Mode>()
+                .ok_or(Error::MissingRegister(registers::RegisterAddr::Mode))?
+                .value();
+            if current == mode {
+                return Ok(());
+            }
+            if current == Modes::Fault {
+                let fault = response
+                    .get::<registers::Fault>()
+                    .ok_or(Error::MissingRegister(registers::RegisterAddr::Fault))?;
+                return Err(Error::Faulted(fault.value()));
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout);
+            }
+            std::thread::sleep(MODE_POLL_INTERVAL.min(timeout - elapsed));
+        }
+    }
+
+    /// Sends a [`Position`] command and polls until the controller reports [`registers::TrajectoryComplete`] for it,
+    /// returning the response that reported completion.
+    ///
+    /// The reply to the command may still report the completion of the previous trajectory, as the controller
+    /// only starts the new trajectory on its next control cycle. So completion is only accepted from a response
+    /// whose [`registers::MillisecondCounter`] has advanced past the one in the reply to the command.
+    /// The command is not repeated while polling, so the watchdog timeout must be longer than the move.
+    ///
+    /// Returns [`Error::Faulted`] if the controller faults, or [`Error::Timeout`] if the trajectory does not
+    /// complete within `timeout`.
+    pub fn send_position_wait_complete(
         &mut self,
         id: u8,
-        frame: impl Into<Frame>,
+        position: Position,
+        timeout: Duration,
     ) -> Result<ResponseFrame, Error<T::Error>> {
-        let frame = frame.into();
-        let arbitration_id = id as u16 | 0x8000;
-        let frame = CanFdFrame {
-            arbitration_id,
-            data: frame.as_bytes()?,
-            brs: Some(!self.disable_brs),
+        let mut query = mode_and_fault();
+        query
+            .add(registers::TrajectoryComplete::read())
+            .add(registers::MillisecondCounter::read());
+        let millis = |response: &ResponseFrame| {
+            response
+                .get::<registers::MillisecondCounter>()
+                .map(|counter| counter.value())
+                .ok_or(Error::MissingRegister(
+                    registers::RegisterAddr::MillisecondCounter,
+                ))
+        };
+        let start = Instant::now();
+        let response = self.send_with_query(id, position, QueryType::Custom(query.clone()))?;
+        let issued = millis(&response)?;
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout);
+            }
+            std::thread::sleep(MODE_POLL_INTERVAL.min(timeout - elapsed));
+            let response = self.query(id, QueryType::Custom(query.clone()))?;
+            check_fault(&response)?;
+            let complete = response
+                .get::<registers::TrajectoryComplete>()
+                .ok_or(Error::MissingRegister(
+                    registers::RegisterAddr::TrajectoryComplete,
+                ))?
+                .value()
+                != 0;
+            // the counter wraps, so compare the difference
+            if complete && millis(&response)?.wrapping_sub(issued) > 0 {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Commands the moteus to `target` in revolutions and polls until it has settled there, returning the
+    /// response that reported it settled.
+    ///
+    /// The controller is settled once its [`registers::Position`] is within `position_tolerance` of `target`
+    /// and the magnitude of its [`registers::Velocity`] is below `velocity_tolerance`. Unlike
+    /// [`Controller::send_position_wait_complete`], which waits for the planned trajectory to end, this
+    /// confirms the motor physically reached the target, which it may not under load.
+    /// The command is not repeated while polling, so the watchdog timeout must be longer than the move.
+    ///
+    /// Returns [`Error::Faulted`] if the controller faults, or [`Error::Timeout`] if it does not settle
+    /// within `timeout`.
+    pub fn move_and_settle(
+        &mut self,
+        id: u8,
+        target: f32,
+        position_tolerance: f32,
+        velocity_tolerance: f32,
+        timeout: Duration,
+    ) -> Result<ResponseFrame, Error<T::Error>> {
+        let mut query = mode_and_fault();
+        query
+            .add(registers::Position::read_with_resolution(Resolution::Float))
+            .add(registers::Velocity::read_with_resolution(Resolution::Float));
+        let command = Position {
+            position: Some(registers::CommandPosition::write(target)?),
+            ..Position::default()
+        };
+        let start = Instant::now();
+        let mut response = self.send_with_query(id, command, QueryType::Custom(query.clone()))?;
+        loop {
+            check_fault(&response)?;
+            let position = response
+                .get::<registers::Position>()
+                .ok_or(Error::MissingRegister(registers::RegisterAddr::Position))?
+                .value();
+            let velocity = response
+                .get::<registers::Velocity>()
+                .ok_or(Error::MissingRegister(registers::RegisterAddr::Velocity))?
+                .value();
+            if (position - target).abs() <= position_tolerance
+                && velocity.abs() <= velocity_tolerance
+            {
+                return Ok(response);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout);
+            }
+            std::thread::sleep(MODE_POLL_INTERVAL.min(timeout - elapsed));
+            response = self.query(id, QueryType::Custom(query.clone()))?;
+        }
+    }
+
+    /// Ramps the velocity of the moteus to zero before stopping it, avoiding the abrupt halt of [`Stop`].
+    ///
+    /// Commands [`Position`] with a NaN position, zero velocity and `decel` as the
+    /// [`registers::AccelerationLimit`] (in rev/s²), polling the [`registers::Velocity`] until it is below
+    /// 0.01 rev/s, then sends a [`Stop`].
+    ///
+    /// Returns [`Error::Faulted`] if the controller faults while ramping down.
+    /// If the velocity is not near zero within `timeout`, the [`Stop`] is sent anyway
+    /// and [`Error::Timeout`] is returned.
+    pub fn smooth_stop(
+        &mut self,
+        id: u8,
+        decel: f32,
+        timeout: Duration,
+    ) -> Result<(), Error<T::Error>> {
+        let command = Position {
+            position: Some(registers::CommandPosition::write(f32::NAN)?),
+            velocity: Some(registers::CommandVelocity::write(0.0)?),
+            acceleration_limit: Some(registers::AccelerationLimit::write(decel)?),
             ..Default::default()
         };
-        self.transport.transmit(frame.into())?;
-        let response = self.transport.receive()?;
-        Ok(response.try_into()?)
+        let mut query = mode_and_fault();
+        query.add(registers::Velocity::read());
+        let start = Instant::now();
+        loop {
+            let response =
+                self.send_with_query(id, command.clone(), QueryType::Custom(query.clone()))?;
+            check_fault(&response)?;
+            let velocity = response
+                .get::<registers::Velocity>()
+                .ok_or(Error::MissingRegister(registers::RegisterAddr::Velocity))?
+                .value();
+            if velocity.abs() < SMOOTH_STOP_VELOCITY {
+                return self.send_no_response(id, Stop);
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                self.send_no_response(id, Stop)?;
+                return Err(Error::Timeout);
+            }
+            std::thread::sleep(MODE_POLL_INTERVAL.min(timeout - elapsed));
+        }
+    }
+
+    /// Plays back a sequence of `(time, position)` points, each commanded at its time since the call.
+    ///
+    /// Each point is sent as a [`Position`] command built from `base`, so `base` sets the
+    /// [`registers::VelocityLimit`], [`registers::AccelerationLimit`] and any other fields of the commands.
+    /// The [`registers::CommandVelocity`] of each point is the velocity towards the next point, and zero
+    /// at the last point. Points must be sorted by time; a point whose time has already passed is sent immediately.
+    ///
+    /// Returns [`Error::Faulted`] as soon as the controller reports a fault.
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use moteus::frame::Position;
+    /// # use moteus::registers::{AccelerationLimit, Writeable};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// let points: Vec<_> = (0..100)
+    ///     .map(|i| (Duration::from_millis(i * 20), (i as f32 * 0.05).sin()))
+    ///     .collect();
+    /// let base = Position {
+    ///     acceleration_limit: Some(AccelerationLimit::write(20.0)?),
+    ///     ..Default::default()
+    /// };
+    /// c.play_trajectory(1, &points, base)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn play_trajectory(
+        &mut self,
+        id: u8,
+        points: &[(Duration, f32)],
+        base: Position,
+    ) -> Result<(), Error<T::Error>> {
+        let query = mode_and_fault();
+        let start = Instant::now();
+        for (i, &(time, position)) in points.iter().enumerate() {
+            let velocity = match points.get(i + 1) {
+                Some(&(next_time, next_position)) if next_time > time => {
+                    (next_position - position) / (next_time - time).as_secs_f32()
+                }
+                _ => 0.0,
+            };
+            let command = Position {
+                position: Some(registers::CommandPosition::write(position)?),
+                velocity: Some(registers::CommandVelocity::write(velocity)?),
+                ..base.clone()
+            };
+            if let Some(wait) = time.checked_sub(start.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            let response = self.send_with_query(id, command, QueryType::Custom(query.clone()))?;
+            check_fault(&response)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the last command sent to `id` again, without a query. No response will be returned.
+    ///
+    /// This can be used to reset the watchdog of the moteus during a slow computation,
+    /// though [`Controller::pet_watchdog`] sends a smaller frame.
+    /// A command is any frame which writes the [`registers::Mode`].
+    /// Returns [`Error::NoCommand`] if no command has been sent to `id`.
+    pub fn repeat_last(&mut self, id: u8) -> Result<(), Error<T::Error>> {
+        let frame = self
+            .last_commands
+            .get(&id)
+            .cloned()
+            .ok_or(Error::NoCommand(id))?;
+        self.transfer_single_no_response(id, frame)
+    }
+
+    /// Resets the watchdog of the moteus with the smallest frame that keeps its current command,
+    /// the last command sent to `id` without its reads. No response will be returned.
+    ///
+    /// The moteus resets the watchdog whenever it receives a command, a frame writing the [`registers::Mode`].
+    /// There is no lighter frame for this, as the command registers left out of a command take their default
+    /// values rather than keeping those of the previous command, so writing only the mode would change the
+    /// control target. The reads of the query, which are only useful when a reply is requested, are dropped.
+    /// Returns [`Error::NoCommand`] if no command has been sent to `id`. A stopped controller has no watchdog,
+    /// so it does not need to be reset.
+    pub fn pet_watchdog(&mut self, id: u8) -> Result<(), Error<T::Error>> {
+        let frame = self
+            .last_commands
+            .get(&id)
+            .cloned()
+            .ok_or(Error::NoCommand(id))?
+            .without_reads();
+        self.transfer_single_no_response(id, frame)
+    }
+
+    /// Restores the protocol state of `id` after an error, such as a parse failure or a timeout, before resuming.
+    ///
+    /// Replies still waiting in the transport are received and discarded, so they are not taken as the reply
+    /// to the next query. This waits for the read timeout of the transport once they run out.
+    /// The last command, fault guard entry, stale check counter and throttled command of `id` are forgotten,
+    /// then the [`registers::Mode`] and [`registers::Fault`] are queried to confirm the controller is alive,
+    /// which also refreshes the fault guard.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::frame::QueryType;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// if let Err(e) = c.query(1, QueryType::Default) {
+    ///     println!("query failed: {e}");
+    ///     c.reset_protocol_state(1)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reset_protocol_state(&mut self, id: u8) -> Result<(), Error<T::Error>> {
+        for _ in 0..MAX_FLUSH_FRAMES {
+            if self.transport.receive().is_err() {
+                break;
+            }
+        }
+        let _ = self.last_commands.remove(&id);
+        if let Some(faults) = &mut self.fault_guard {
+            let _ = faults.remove(&id);
+        }
+        if let Some(counters) = &mut self.stale_check {
+            let _ = counters.remove(&id);
+        }
+        if let Some(throttle) = &mut self.throttle {
+            throttle.forget(id);
+        }
+        let _ = self.query(id, QueryType::Custom(mode_and_fault()))?;
+        Ok(())
+    }
+
+    /// Returns an iterator which sends `query` to the moteus once every `period`, yielding each [`ResponseFrame`].
+    ///
+    /// The first query is sent immediately. Each following call to `next` sleeps until the next period boundary.
+    /// If the caller falls behind by more than a period, the schedule restarts from the current time
+    /// instead of sending a burst of queries.
+    ///
+    /// ```rust,no_run
+    /// # use moteus::frame::QueryType;
+    /// # use moteus::registers::Position;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// for response in c
+    ///     .telemetry_stream(1, QueryType::Default, std::time::Duration::from_millis(10))
+    ///     .take(100)
+    /// {
+    ///     println!("{:?}", response?.get::<Position>());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn telemetry_stream(
+        &mut self,
+        id: u8,
+        query: QueryType,
+        period: Duration,
+    ) -> TelemetryStream<'_, T> {
+        TelemetryStream {
+            controller: self,
+            id,
+            query,
+            period,
+            next: None,
+        }
+    }
+
+    /// Writes `data` to the diagnostic stream of the moteus. No response will be returned.
+    ///
+    /// The data is split across multiple frames if required.
+    pub fn diagnostic_write(&mut self, id: u8, data: &[u8]) -> Result<(), Error<T::Error>> {
+        for chunk in data.chunks(MAX_DIAGNOSTIC_CHUNK) {
+            self.transmit_raw(
+                id,
+                diagnostic::write_frame(DIAGNOSTIC_CHANNEL, chunk),
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Polls the diagnostic stream of the moteus once and returns the bytes available (which may be none).
+    pub fn diagnostic_read(&mut self, id: u8) -> Result<Vec<u8>, Error<T::Error>> {
+        let poll = diagnostic::poll_frame(DIAGNOSTIC_CHANNEL, MAX_DIAGNOSTIC_CHUNK as u8);
+        self.transmit_raw(id, poll, true)?;
+        let response: CanFdFrame = self.transport.receive()?.into();
+        Ok(diagnostic::parse_reply(&response.data, DIAGNOSTIC_CHANNEL)?)
+    }
+
+    /// Sends a command (such as `conf get motor.poles`) on the diagnostic stream and returns the first line of the reply.
+    ///
+    /// A newline is appended to the command.
+    /// Replies starting with `ERR` are returned as [`Error::Diagnostic`].
+    /// If no reply is received [`Error::NoResponse`] is returned.
+    pub fn diagnostic_command(&mut self, id: u8, command: &str) -> Result<String, Error<T::Error>> {
+        self.diagnostic_write(id, format!("{command}\n").as_bytes())?;
+        let mut reply = Vec::new();
+        let mut empty_polls = 0;
+        loop {
+            let data = self.diagnostic_read(id)?;
+            if data.is_empty() {
+                empty_polls += 1;
+                if empty_polls >= diagnostic::MAX_EMPTY_POLLS {
+                    return Err(Error::NoResponse);
+                }
+                continue;
+            }
+            empty_polls = 0;
+            reply.extend(data);
+            if let Some(line) = diagnostic::take_line(&mut reply) {
+                if line.starts_with("ERR") {
+                    return Err(Error::Diagnostic(line));
+                }
+                return Ok(line);
+            }
+        }
+    }
+
+    /// Sends a `d` command (such as `d stop` or `d pos nan 0 1`) on the diagnostic stream.
+    /// The `d ` prefix is added if it is missing.
+    ///
+    /// Returns the reply if it is `OK`, otherwise the reply is returned as [`Error::Diagnostic`].
+    pub fn diagnostic_d_command(
+        &mut self,
+        id: u8,
+        command: &str,
+    ) -> Result<String, Error<T::Error>> {
+        let reply = self.diagnostic_command(id, &diagnostic::d_command(command))?;
+        if !diagnostic::is_ok(&reply) {
+            return Err(Error::Diagnostic(reply));
+        }
+        Ok(reply)
+    }
+
+    /// Reads a configuration value (such as `motor.poles`) with `conf get`.
+    pub fn get_config_f32(&mut self, id: u8, name: &str) -> Result<f32, Error<T::Error>> {
+        let reply = self.diagnostic_command(id, &format!("conf get {name}"))?;
+        diagnostic::parse_f32(&reply).ok_or(Error::Diagnostic(reply))
+    }
+
+    /// Sets a configuration value (such as `servo.max_current_A`) with `conf set`.
+    ///
+    /// The value is not persisted across a power cycle unless `conf write` is sent with [`Controller::diagnostic_command`].
+    pub fn set_config_f32(
+        &mut self,
+        id: u8,
+        name: &str,
+        value: f32,
+    ) -> Result<(), Error<T::Error>> {
+        let reply = self.diagnostic_command(id, &format!("conf set {name} {value}"))?;
+        if reply != "OK" {
+            return Err(Error::Diagnostic(reply));
+        }
+        Ok(())
+    }
+
+    /// Starts an inductance measurement with the `d ind` diagnostic command, as done by `moteus_tool` during calibration.
+    ///
+    /// The controller enters [`Modes::MeasureInd`], applying a square wave of `voltage` volts to the d axis which
+    /// alternates every `period` control cycles. Poll the [`registers::DCurrent`] to compute the inductance,
+    /// and send a [`Stop`] when done. See [`crate::frame::MeasureInductance`].
+    pub fn measure_inductance(
+        &mut self,
+        id: u8,
+        voltage: f32,
+        period: u8,
+    ) -> Result<(), Error<T::Error>> {
+        let _ = self.diagnostic_d_command(id, &format!("ind {voltage} {period}"))?;
+        Ok(())
+    }
+
+    /// Reads the [`registers::Fault`], [`registers::EncoderValidity`] and the position and velocity of each encoder,
+    /// returning an [`EncoderDiagnostics`] reporting the health of each encoder.
+    ///
+    /// Useful when the controller reports [`Faults::EncoderFault`], to find which encoder failed.
+    pub fn encoder_diagnostics(&mut self, id: u8) -> Result<EncoderDiagnostics, Error<T::Error>> {
+        let response = self.query(id, QueryType::Custom(EncoderDiagnostics::query()))?;
+        EncoderDiagnostics::from_response(&response).ok_or(Error::MissingRegister(
+            registers::RegisterAddr::EncoderValidity,
+        ))
+    }
+
+    /// Reads the [`registers::EncoderValidity`] and the position and velocity of each encoder in a single query,
+    /// returning them as [`EncoderReadings`].
+    ///
+    /// Use [`Controller::encoder_diagnostics`] to also read the fault of the controller.
+    pub fn read_encoders(&mut self, id: u8) -> Result<EncoderReadings, Error<T::Error>> {
+        let response = self.query(id, QueryType::Custom(EncoderReadings::query()))?;
+        EncoderReadings::from_response(&response).ok_or(Error::MissingRegister(
+            registers::RegisterAddr::EncoderValidity,
+        ))
+    }
+
+    /// Reads which encoder source the output position is derived from, the `motor_position.output.source` config.
+    ///
+    /// The sources are those configured in `motor_position.sources`, so the index matches the encoders of
+    /// [`EncoderDiagnostics`]. For a joint with an encoder on the motor and another on the output, this is the
+    /// encoder reported as the [`registers::Position`].
+    pub fn output_source(&mut self, id: u8) -> Result<usize, Error<T::Error>> {
+        Ok(self.get_config_f32(id, OUTPUT_SOURCE_CONFIG)? as usize)
+    }
+
+    /// Selects the encoder source the output position is derived from, setting `motor_position.output.source`.
+    ///
+    /// The output position jumps to the reading of the new source, so send a [`Stop`] first.
+    /// As with [`Controller::set_config_f32`] it is not persisted across a power cycle unless `conf write` is sent.
+    pub fn set_output_source(&mut self, id: u8, source: usize) -> Result<(), Error<T::Error>> {
+        self.set_config_f32(id, OUTPUT_SOURCE_CONFIG, source as f32)
+    }
+
+    /// Reads the [`registers::Voltage`] of the moteus in volts at [`Resolution::Float`],
+    /// finer than the `Int8` of the default query.
+    ///
+    /// Use [`crate::VoltageThresholds::classify`] to check the reading against under and over-voltage limits.
+    pub fn bus_voltage(&mut self, id: u8) -> Result<f32, Error<T::Error>> {
+        let response = self.query(
+            id,
+            QueryType::Custom(Frame::with_builder(|b| {
+                b.add(registers::Voltage::read_with_resolution(Resolution::Float));
+            })),
+        )?;
+        Ok(response
+            .get::<registers::Voltage>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Voltage))?
+            .value())
+    }
+
+    /// Reads the board [`registers::Temperature`] of the moteus in °C at [`Resolution::Float`].
+    pub fn temperature(&mut self, id: u8) -> Result<f32, Error<T::Error>> {
+        let response = self.query(
+            id,
+            QueryType::Custom(Frame::with_builder(|b| {
+                b.add(registers::Temperature::read_with_resolution(
+                    Resolution::Float,
+                ));
+            })),
+        )?;
+        Ok(response
+            .get::<registers::Temperature>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Temperature))?
+            .value())
+    }
+
+    /// Returns the board [`registers::Temperature`] of the moteus as a fraction of the temperature at which it
+    /// faults with [`Faults::OverTemperature`], so 1.0 is at the threshold.
+    ///
+    /// If `fault_temperature` is [`None`] the threshold is read from `servo.fault_temperature` with
+    /// [`Controller::get_config_f32`]. Reading the config is slow, so read it once and pass it in when polling.
+    /// A threshold which is not finite and positive returns [`Error::InvalidFaultTemperature`].
+    /// The controller already derates the current above `servo.derate_temperature`, so reduce the load before then
+    /// to avoid it.
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut c = moteus::Controller::fdcanusb("/dev/fdcanusb", moteus::serial2::KeepSettings, false)?;
+    /// let threshold = c.get_config_f32(1, "servo.fault_temperature")?;
+    /// if c.temperature_fraction(1, Some(threshold))? > 0.8 {
+    ///     println!("controller 1 is running hot");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn temperature_fraction(
+        &mut self,
+        id: u8,
+        fault_temperature: Option<f32>,
+    ) -> Result<f32, Error<T::Error>> {
+        let fault_temperature = match fault_temperature {
+            Some(fault_temperature) => fault_temperature,
+            None => self.get_config_f32(id, "servo.fault_temperature")?,
+        };
+        // a fraction of a zero, negative or NaN threshold would look like a valid reading
+        if !(fault_temperature.is_finite() && fault_temperature > 0.0) {
+            return Err(Error::InvalidFaultTemperature(fault_temperature));
+        }
+        Ok(self.temperature(id)? / fault_temperature)
+    }
+
+    /// Reads the [`registers::ClockTrim`] and [`registers::Temperature`] of the moteus together,
+    /// for compensating the clock over temperature.
+    pub fn clock_trim(&mut self, id: u8) -> Result<ClockTrimStatus, Error<T::Error>> {
+        let response = self.query(
+            id,
+            QueryType::Custom(Frame::with_builder(|b| {
+                b.add(registers::ClockTrim::read())
+                    .add(registers::Temperature::read());
+            })),
+        )?;
+        let trim = response
+            .get::<registers::ClockTrim>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::ClockTrim))?
+            .value();
+        let temperature = response
+            .get::<registers::Temperature>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Temperature))?
+            .value();
+        Ok(ClockTrimStatus { trim, temperature })
+    }
+
+    /// Writes the [`registers::ClockTrim`] of the moteus. No response will be returned.
+    pub fn set_clock_trim(&mut self, id: u8, trim: i8) -> Result<(), Error<T::Error>> {
+        self.write::<registers::ClockTrim>(id, trim)
+    }
+
+    /// Reads the [`registers::RegisterMapVersion`] of the moteus.
+    ///
+    /// This crate only knows the register addresses of [`registers::REGISTER_MAP_VERSION`],
+    /// so a warning is logged if the controller reports another version, as its replies may be misread.
+    pub fn register_map_version(&mut self, id: u8) -> Result<u32, Error<T::Error>> {
+        let version = self.read::<registers::RegisterMapVersion>(id)?.value();
+        if version != registers::REGISTER_MAP_VERSION {
+            log::warn!(
+                "controller {id} uses register map version {version}, expected {}",
+                registers::REGISTER_MAP_VERSION
+            );
+        }
+        Ok(version)
+    }
+
+    /// Reads the human readable firmware version of the moteus with `tel get firmware`.
+    pub fn firmware_version(&mut self, id: u8) -> Result<String, Error<T::Error>> {
+        let reply = self.diagnostic_command(id, "tel get firmware")?;
+        diagnostic::parse_version(&reply).ok_or(Error::Diagnostic(reply))
+    }
+
+    fn transmit_raw(&mut self, id: u8, data: Vec<u8>, reply: bool) -> Result<(), Error<T::Error>> {
+        let frame = self.can_frame(id, data, reply);
+        self.transport.transmit(frame.into())?;
+        Ok(())
+    }
+
+    /// Transmits a payload from [`Controller::prepare`], remembering its command once it was transmitted.
+    fn transmit_command(
+        &mut self,
+        id: u8,
+        data: Vec<u8>,
+        command: Option<FrameBuilder>,
+        reply: bool,
+    ) -> Result<(), Error<T::Error>> {
+        self.transmit_raw(id, data, reply)?;
+        if let Some(command) = command {
+            self.remember(id, command);
+        }
+        Ok(())
+    }
+
+    /// Wraps a payload in the [`CanFdFrame`] sent to `id`
+    fn can_frame(&self, id: u8, data: Vec<u8>, reply: bool) -> CanFdFrame {
+        // the source id and the `can_prefix` bits are left as 0, see the docs of `Controller`
+        let mut arbitration_id = id as u16;
+        if reply {
+            arbitration_id |= 0x8000;
+        }
+        CanFdFrame {
+            arbitration_id,
+            data,
+            brs: Some(!self.disable_brs),
+            ..Default::default()
+        }
+    }
+
+    /// Checks the frame against the fault guard and the throttle and serializes it.
+    /// The frame is also returned if it is a command, to be remembered once it was transmitted.
+    fn prepare(&mut self, id: u8, frame: FrameBuilder) -> Result<Prepared, Error<T::Error>> {
+        let frame = self.shape(id, frame)?;
+        let command = frame
+            .get_write::<registers::Mode>()
+            .is_some()
+            .then(|| frame.clone());
+        let data = self.serialize(frame)?;
+        if let Some(command) = &command {
+            self.check_throttle(id, command)?;
+        }
+        Ok((data, command))
+    }
+
+    /// Checks a command to `id` against the [`Throttle`], sleeping or holding it as set by its policy.
+    /// A [`Stop`] is always sent immediately.
+    fn check_throttle(&mut self, id: u8, command: &FrameBuilder) -> Result<(), Error<T::Error>> {
+        let Some(throttle) = &mut self.throttle else {
+            return Ok(());
+        };
+        let stop = command
+            .get_write::<registers::Mode>()
+            .is_some_and(|m| m.value() == Modes::Stopped);
+        if let Some(remaining) = throttle.remaining_at(id, Instant::now()).filter(|_| !stop) {
+            match throttle.policy() {
+                ThrottlePolicy::Drop => return Err(Error::Throttled(remaining)),
+                ThrottlePolicy::Coalesce => {
+                    throttle.hold(id, command.clone());
+                    return Err(Error::Throttled(remaining));
+                }
+                ThrottlePolicy::Block => std::thread::sleep(remaining),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the frame against the fault guard and applies the resolution policy and the torque limit of `id`.
+    fn shape(&mut self, id: u8, frame: FrameBuilder) -> Result<FrameBuilder, Error<T::Error>> {
+        let mode = frame.get_write::<registers::Mode>().map(|m| m.value());
+        if let Some(faults) = &mut self.fault_guard {
+            match mode {
+                Some(Modes::Stopped) => {
+                    let _ = faults.remove(&id);
+                }
+                Some(_) => {
+                    if let Some(fault) = faults.get(&id) {
+                        return Err(Error::Faulted(*fault));
+                    }
+                }
+                None => {}
+            }
+        }
+        let mut frame = match &self.resolution_policy {
+            Some(policy) => policy.apply(frame),
+            None => frame,
+        };
+        if let (Some(Modes::Position), Some(&limit)) = (mode, self.max_torque.get(&id)) {
+            let requested = frame
+                .get_write::<registers::CommandPositionMaxTorque>()
+                .map(|t| t.value());
+            // NaN means no limit
+            if !requested.is_some_and(|t| t <= limit) {
+                frame.remove(registers::RegisterAddr::CommandPositionMaxTorque);
+                frame.add(registers::CommandPositionMaxTorque::write(limit)?);
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Serializes the frame, checking its length against the CAN-FD and configured payload limits.
+    fn serialize(&mut self, frame: FrameBuilder) -> Result<Vec<u8>, Error<T::Error>> {
+        let data = frame.build().as_bytes()?;
+        let len = data.len();
+        if len > 64 {
+            return Err(Error::InvalidFrameLength(len));
+        }
+        if let Some(max) = self.max_payload {
+            if len > max {
+                return Err(Error::PayloadTooLarge { len, max });
+            }
+        }
+        Ok(data)
+    }
+
+    /// Remembers a frame which writes the [`registers::Mode`] as the last command sent to `id`,
+    /// recording the time it was sent with the throttle and the watchdog guard.
+    fn remember(&mut self, id: u8, command: FrameBuilder) {
+        if let Some(throttle) = &mut self.throttle {
+            throttle.sent_at(id, Instant::now());
+        }
+        if let Some(guard) = &mut self.watchdog_guard {
+            match command.get_write::<registers::Mode>().map(|m| m.value()) {
+                Some(Modes::Stopped) => guard.stopped(id),
+                _ => guard.commanded(id),
+            }
+        }
+        let _ = self.last_commands.insert(id, command);
+    }
+
+    /// Updates the fault guard from a response
+    fn observe(&mut self, id: u8, response: &ResponseFrame) {
+        if let Some(counters) = &mut self.stale_check {
+            if let Some(counter) = response.get::<registers::MillisecondCounter>() {
+                let counter = counter.value();
+                let stale = counters.get(&id).is_some_and(|(last, _)| *last == counter);
+                if stale {
+                    log::warn!("stale response from {id}: millisecond counter still at {counter}");
+                }
+                let _ = counters.insert(id, (counter, stale));
+            }
+        }
+        let Some(faults) = &mut self.fault_guard else {
+            return;
+        };
+        match response.get::<registers::Fault>().map(|f| f.value()) {
+            Some(Faults::Success) => {
+                let _ = faults.remove(&id);
+            }
+            Some(fault) => {
+                let _ = faults.insert(id, fault);
+            }
+            None => {}
+        }
+    }
+
+    fn transfer_single_no_response(
+        &mut self,
+        id: u8,
+        frame: FrameBuilder,
+    ) -> Result<(), Error<T::Error>> {
+        let (data, command) = self.prepare(id, frame)?;
+        self.transmit_command(id, data, command, false)
+    }
+    fn merge_query(&self, frame: FrameBuilder, query: QueryType) -> FrameBuilder {
+        match query {
+            QueryType::Default => frame.merge(self.default_query.clone()),
+            QueryType::DefaultAnd(q_frame) => {
+                frame.merge(self.default_query.clone()).merge(q_frame)
+            }
+            QueryType::Custom(q_frame) => frame.merge(q_frame),
+        }
+    }
+
+    /// Sends the frame requesting a reply, and returns the reply without parsing it.
+    fn transfer_single_raw(
+        &mut self,
+        id: u8,
+        frame: FrameBuilder,
+    ) -> Result<CanFdFrame, Error<T::Error>> {
+        let (data, command) = self.prepare(id, frame)?;
+        self.transfer_payload(id, data, command)
+    }
+
+    /// Sends `frame` to `id` for [`Controller::send_same_to`], reusing the payload in `built`
+    /// if the shaped frame is unchanged from the previous id.
+    fn send_shared(
+        &mut self,
+        id: u8,
+        frame: &FrameBuilder,
+        built: &mut Option<(FrameBuilder, Vec<u8>)>,
+    ) -> Result<ResponseFrame, Error<T::Error>> {
+        let shaped = self.shape(id, frame.clone())?;
+        let data = match built {
+            Some((previous, data)) if *previous == shaped => data.clone(),
+            _ => {
+                let data = self.serialize(shaped.clone())?;
+                *built = Some((shaped.clone(), data.clone()));
+                data
+            }
+        };
+        let command = shaped
+            .get_write::<registers::Mode>()
+            .is_some()
+            .then_some(shaped);
+        if let Some(command) = &command {
+            self.check_throttle(id, command)?;
+        }
+        let response = self.transfer_payload(id, data, command)?;
+        let response = ResponseFrame::from_can_frame(&response, &self.custom_registers)?;
+        self.observe(id, &response);
+        Ok(response)
+    }
+
+    /// Sends an already serialized payload requesting a reply, and returns the reply without parsing it.
+    fn transfer_payload(
+        &mut self,
+        id: u8,
+        data: Vec<u8>,
+        command: Option<FrameBuilder>,
+    ) -> Result<CanFdFrame, Error<T::Error>> {
+        let start = Instant::now();
+        self.transmit_command(id, data, command, true)?;
+        let response: CanFdFrame = self.transport.receive()?.into();
+        if let Some(stats) = &mut self.latency_stats {
+            stats.record(start.elapsed());
+        }
+        Ok(response)
+    }
+
+    fn transfer_single_with_response(
+        &mut self,
+        id: u8,
+        frame: FrameBuilder,
+    ) -> Result<ResponseFrame, Error<T::Error>> {
+        let response = self.transfer_single_raw(id, frame)?;
+        let response = ResponseFrame::from_can_frame(&response, &self.custom_registers)?;
+        self.observe(id, &response);
+        Ok(response)
+    }
+}
+
+/// The id of a controller with the result of a request to it, returned by [`Controller::send_same_to`]
+pub type IdResponse<E> = (u8, Result<ResponseFrame, Error<E>>);
+
+/// A serialized payload with the command it holds, if any
+type Prepared = (Vec<u8>, Option<FrameBuilder>);
+
+/// The clock trim and board temperature of a moteus, read with [`Controller::clock_trim`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockTrimStatus {
+    /// The [`registers::ClockTrim`] of the clock
+    pub trim: i8,
+    /// The [`registers::Temperature`] of the board in °C
+    pub temperature: f32,
+}
+
+/// The interval between polls in [`Controller::wait_for_mode`], [`Controller::smooth_stop`]
+/// and [`Controller::send_position_wait_complete`]
+const MODE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The velocity in rev/s below which [`Controller::smooth_stop`] considers the moteus stopped
+const SMOOTH_STOP_VELOCITY: f32 = 0.01;
+
+/// The config selecting the encoder source of the output position
+const OUTPUT_SOURCE_CONFIG: &str = "motor_position.output.source";
+
+/// The most stale replies discarded by [`Controller::reset_protocol_state`], in case the transport never runs out
+const MAX_FLUSH_FRAMES: usize = 64;
+
+/// The destination id which every controller on the bus accepts, used by [`Controller::query_all`]
+const BROADCAST_ID: u8 = 0x7f;
+
+/// A query for the [`registers::Mode`] and [`registers::Fault`]
+fn mode_and_fault() -> FrameBuilder {
+    let mut query = Frame::builder();
+    query.add(registers::Mode::read());
+    query.add(registers::Fault::read());
+    query
+}
+
+/// Returns [`Error::Faulted`] if the response reports [`Modes::Fault`]
+fn check_fault<E>(response: &ResponseFrame) -> Result<(), Error<E>> {
+    if response.get::<registers::Mode>().map(|mode| mode.value()) == Some(Modes::Fault) {
+        let fault = response
+            .get::<registers::Fault>()
+            .ok_or(Error::MissingRegister(registers::RegisterAddr::Fault))?;
+        return Err(Error::Faulted(fault.value()));
+    }
+    Ok(())
+}
+
+/// An iterator which queries a controller at a fixed rate. Created by [`Controller::telemetry_stream`].
+pub struct TelemetryStream<'a, T> {
+    controller: &'a mut Controller<T>,
+    id: u8,
+    query: QueryType,
+    period: Duration,
+    next: Option<Instant>,
+}
+
+impl<T, F> Iterator for TelemetryStream<'_, T>
+where
+    T: crate::transport::Transport<Frame = F>,
+    F: From<CanFdFrame> + Into<CanFdFrame>,
+{
+    type Item = Result<ResponseFrame, Error<T::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(next) = self.next {
+            let now = Instant::now();
+            if next > now {
+                std::thread::sleep(next - now);
+            }
+        }
+        let start = Instant::now();
+        self.next = Some(match self.next {
+            Some(next) if next + self.period > start => next + self.period,
+            _ => start + self.period,
+        });
+        Some(self.controller.query(self.id, self.query.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::transport::Transport;
+    use crate::Resolution;
+    use crate::WatchdogStatus;
+    use std::collections::VecDeque;
+
+    /// Records transmitted frames and replies with queued frames.
+    #[derive(Default)]
+    struct MockTransport {
+        sent: Vec<CanFdFrame>,
+        replies: VecDeque<Vec<u8>>,
+        /// The source ids of the replies, 1 if empty
+        sources: VecDeque<u8>,
+        /// Returns an I/O error instead of [`Error::NoResponse`] once the replies run out
+        io_error: bool,
+        /// Fails every transmit with an I/O error
+        fail_transmit: bool,
+    }
+
+    impl Transport for MockTransport {
+        type Error = std::io::Error;
+        type Frame = CanFdFrame;
+
+        fn transmit(&mut self, frame: Self::Frame) -> Result<(), Error<Self::Error>> {
+            if self.fail_transmit {
+                return Err(Error::Transport(std::io::ErrorKind::BrokenPipe.into()));
+            }
+            self.sent.push(frame);
+            Ok(())
+        }
+
+        fn receive(&mut self) -> Result<Self::Frame, Error<Self::Error>> {
+            let Some(data) = self.replies.pop_front() else {
+                if self.io_error {
+                    return Err(Error::Transport(std::io::ErrorKind::BrokenPipe.into()));
+                }
+                return Err(Error::NoResponse);
+            };
+            let source = self.sources.pop_front().unwrap_or(1);
+            Ok(CanFdFrame {
+                arbitration_id: (source as u16) << 8,
+                data,
+                ..Default::default()
+            })
+        }
+    }
+
+    fn controller(replies: impl IntoIterator<Item = Vec<u8>>) -> Controller<MockTransport> {
+        let transport = MockTransport {
+            replies: replies.into_iter().collect(),
+            ..Default::default()
+        };
+        Controller::new(transport, false)
+    }
+
+    #[test]
+    fn test_fault_guard() {
+        // Mode: Fault, Fault: OverTemperature
+        let faulted = vec![0x21, 0x00, 0x01, 0x21, 0x0f, 38];
+        let mut c = controller([faulted]);
+        c.set_fault_guard(true);
+        let _ = c.query(1, QueryType::Default).unwrap();
+
+        assert!(matches!(
+            c.send_no_response(1, Position::hold()),
+            Err(Error::Faulted(Faults::OverTemperature))
+        ));
+        c.send_no_response(2, Position::hold()).unwrap();
+        c.send_no_response(1, Stop).unwrap();
+        c.send_no_response(1, Position::hold()).unwrap();
+    }
+
+    #[test]
+    fn test_fault_hint() {
+        let error: Error<std::io::Error> = Error::Faulted(Faults::StopPositionDeprecated);
+        assert!(error
+            .to_string()
+            .contains("velocity or acceleration limits"));
+        let error: Error<std::io::Error> = Error::Faulted(Faults::OverTemperature);
+        assert_eq!(error.to_string(), "controller is faulted: OverTemperature");
+    }
+
+    #[test]
+    fn test_read_all() {
+        let reply = |position: f32| [&[0x2d, 0x01][..], &position.to_le_bytes()].concat();
+        // the second reply is missing the register
+        let mut c = controller([reply(0.5), vec![], reply(-0.25)]);
+        let positions = c
+            .read_all::<registers::Position>(&[1, 2, 3, 4])
+            .into_iter()
+            .map(|(id, position)| (id, position.map(|p| p.value())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(1, Some(0.5)), (2, None), (3, Some(-0.25)), (4, None)]
+        );
+        assert_eq!(c.transport.sent[3].data, vec![0x1d, 0x01]);
+    }
+
+    #[test]
+    fn test_fault_report() {
+        // Mode: Fault, Fault: OverTemperature
+        let faulted = vec![0x21, 0x00, 0x01, 0x21, 0x0f, 38];
+        // Mode: Stopped, Fault: Success
+        let ok = vec![0x21, 0x00, 0x00, 0x21, 0x0f, 0x00];
+        let mut c = controller([faulted, ok]);
+        assert_eq!(
+            c.fault_report(&[1, 3, 2]),
+            vec![
+                (1, Some(Faults::OverTemperature)),
+                (3, Some(Faults::Success)),
+                (2, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_driver_fault_report() {
+        let reply = |fault: u8, driver_fault1: u32| {
+            let mut buf = vec![0x21, 0x0f, fault, 0x2a, 0xc0, 0x02];
+            buf.extend(driver_fault1.to_le_bytes());
+            buf.extend(0u32.to_le_bytes());
+            buf
+        };
+        // 1 is healthy, 2 has a driver fault and 3 does not respond
+        let mut c = controller([reply(0, 0), reply(33, 1 << 9)]);
+        let report = c.driver_fault_report(&[1, 2, 3]);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].0, 2);
+        assert_eq!(
+            report[0].1.unwrap().conditions(),
+            vec![crate::DriverCondition::VdsOvercurrent]
+        );
+        assert_eq!(report[1], (3, None));
+    }
+
+    #[test]
+    fn test_is_ready() {
+        let reply = |mode: u8, fault: u8, validity: u8| {
+            vec![0x21, 0x00, mode, 0x21, 0x0f, fault, 0x21, 0x58, validity]
+        };
+        let mut c = controller([
+            reply(0, 0, 0b001),
+            reply(10, 0, 0b011),
+            reply(3, 0, 0b001),
+            reply(1, 38, 0b001),
+            reply(0, 0, 0b010),
+        ]);
+        assert!(c.is_ready(1).unwrap());
+        assert!(c.is_ready(1).unwrap());
+        assert!(!c.is_ready(1).unwrap());
+        assert!(!c.is_ready(1).unwrap());
+        assert!(!c.is_ready(1).unwrap());
+        assert!(c.transport.sent[0].data.ends_with(&[0x11, 0x58]));
+        assert!(matches!(c.is_ready(1), Err(Error::NoResponse)));
+    }
+
+    #[test]
+    fn test_max_payload() {
+        let mut c = controller([]);
+        c.set_max_payload(Some(4));
+        assert!(matches!(
+            c.send_no_response(1, Position::hold()),
+            Err(Error::PayloadTooLarge { len: 9, max: 4 })
+        ));
+        assert!(c.transport.sent.is_empty());
+        c.send_no_response(1, Stop).unwrap();
+
+        // 20 float registers do not fit in a CAN-FD frame
+        let mut frame = Frame::builder();
+        registers::RegisterAddr::ALL
+            .iter()
+            .filter_map(|addr| addr.write_f64(0.0, Resolution::Float).ok())
+            .take(20)
+            .for_each(|register| {
+                frame.add(register);
+            });
+        c.set_max_payload(None);
+        assert!(matches!(
+            c.send_no_response(1, frame),
+            Err(Error::InvalidFrameLength(_))
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_mode() {
+        // Mode: Enabling, Position and Fault with Fault: Success
+        let enabling = vec![0x21, 0x00, 0x02, 0x21, 0x0f, 0x00];
+        let position = vec![0x21, 0x00, 0x0a, 0x21, 0x0f, 0x00];
+        let faulted = vec![0x21, 0x00, 0x01, 0x21, 0x0f, 38];
+        let mut c = controller([enabling.clone(), position, faulted, enabling]);
+        c.wait_for_mode(1, Modes::Position, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(c.transport.sent.len(), 2);
+        assert!(matches!(
+            c.wait_for_mode(1, Modes::Position, Duration::from_secs(1)),
+            Err(Error::Faulted(Faults::OverTemperature))
+        ));
+        assert!(matches!(
+            c.wait_for_mode(1, Modes::Position, Duration::ZERO),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_smooth_stop() {
+        // Mode: Position, Fault: Success, Velocity (Float)
+        let moving = vec![
+            0x21, 0x00, 0x0a, 0x21, 0x0f, 0x00, 0x2d, 0x02, 0x00, 0x00, 0x80, 0x3f,
+        ];
+        let stopped = vec![
+            0x21, 0x00, 0x0a, 0x21, 0x0f, 0x00, 0x2d, 0x02, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut c = controller([moving.clone(), stopped]);
+        c.smooth_stop(1, 2.0, Duration::from_secs(1)).unwrap();
+        assert_eq!(c.transport.sent.len(), 3);
+        let stop: FrameBuilder = Stop.into();
+        assert_eq!(c.transport.sent[2].data, stop.build().as_bytes().unwrap());
+
+        let mut c = controller([moving]);
+        assert!(matches!(
+            c.smooth_stop(1, 2.0, Duration::ZERO),
+            Err(Error::Timeout)
+        ));
+        assert_eq!(c.transport.sent.len(), 2);
+    }
+
+    #[test]
+    fn test_stale_check() {
+        // MillisecondCounter (Int32)
+        let counter = |ms: i32| [&[0x29, 0x70][..], &ms.to_le_bytes()].concat();
+        let mut c = controller([counter(10), counter(10), counter(20)]);
+        c.set_stale_check(true);
+        c.set_stale_check(true);
+        let _ = c.query(1, QueryType::Default).unwrap();
+        assert!(!c.is_stale(1));
+        let _ = c.query(1, QueryType::Default).unwrap();
+        assert!(c.is_stale(1));
+        let _ = c.query(1, QueryType::Default).unwrap();
+        assert!(!c.is_stale(1));
+        // enabling twice only adds the counter to the default query once
+        let counters = |c: &Controller<MockTransport>| {
+            c.default_query()
+                .registers()
+                .filter(|reg| reg.address == registers::RegisterAddr::MillisecondCounter)
+                .count()
+        };
+        assert_eq!(counters(&c), 1);
+        c.set_stale_check(false);
+        assert_eq!(counters(&c), 0);
+
+        // a counter in the default query of the user is kept
+        let query = Frame::with_builder(|b| {
+            b.add(registers::MillisecondCounter::read());
+        });
+        let mut c = Controller::with_query(MockTransport::default(), false, query);
+        c.set_stale_check(true);
+        c.set_stale_check(false);
+        assert_eq!(counters(&c), 1);
+    }
+
+    #[test]
+    fn test_send_with_query_ignore() {
+        // a reply that would fail to parse is read and discarded
+        let mut c = controller([vec![0xff, 0xff], vec![0x21, 0x00, 0x0a]]);
+        c.send_with_query_ignore(1, Position::hold(), QueryType::Default)
+            .unwrap();
+        assert_eq!(c.transport.sent[0].arbitration_id, 0x8001);
+        assert_eq!(c.transport.replies.len(), 1);
+        let response = c.query(1, QueryType::Default).unwrap();
+        assert!(response.get::<registers::Mode>().is_some());
+    }
+
+    #[test]
+    fn test_send_confirmed() {
+        let mut c = controller([vec![]]);
+        let response = c.send_confirmed(1, Stop).unwrap();
+        assert!(response.get::<registers::Mode>().is_none());
+        let sent = &c.transport.sent[0];
+        assert_eq!(sent.arbitration_id, 0x8001);
+        assert_eq!(sent.data, vec![0x01, 0x00, 0x00]);
+
+        // no reply, or a reply from another controller
+        assert!(matches!(c.send_confirmed(1, Stop), Err(Error::NoResponse)));
+        c.transport.replies.push_back(vec![]);
+        c.transport.sources.push_back(2);
+        assert!(matches!(c.send_confirmed(1, Stop), Err(Error::NoResponse)));
+    }
+
+    #[test]
+    fn test_send_with_query_raw() {
+        let reply = vec![0x21, 0x00, 0x0a, 0x50];
+        let mut c = controller([reply.clone()]);
+        let (response, raw) = c
+            .send_with_query_raw(1, Position::hold(), QueryType::Default)
+            .unwrap();
+        assert_eq!(raw.data, reply);
+        assert_eq!(
+            response.get::<registers::Mode>().unwrap().value(),
+            Modes::Position
+        );
+    }
+
+    #[test]
+    fn test_send_same_to() {
+        let reply = vec![0x21, 0x00, 0x0a];
+        let mut c = controller([reply.clone(), reply.clone(), reply]);
+        c.set_max_torque(3, Some(1.0));
+        let responses = c.send_same_to(&[1, 2, 3, 4], Position::hold(), QueryType::Default);
+        assert_eq!(
+            responses.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert!(responses[..3].iter().all(|(_, r)| r.is_ok()));
+        // 4 did not reply
+        assert!(matches!(responses[3].1, Err(Error::NoResponse)));
+        let sent = &c.transport.sent;
+        assert_eq!(sent[0].arbitration_id, 0x8001);
+        assert_eq!(sent[1].arbitration_id, 0x8002);
+        assert_eq!(sent[0].data, sent[1].data);
+        // the torque limit of id 3 changes its payload
+        assert_ne!(sent[0].data, sent[2].data);
+        assert!(c.last_commands.contains_key(&2));
+    }
+
+    #[test]
+    fn test_query_all() {
+        let reply = vec![0x21, 0x00, 0x0a];
+        let mut c = controller([reply.clone(), reply.clone(), reply]);
+        c.transport.sources = VecDeque::from([3, 1, 2]);
+        let responses = c
+            .query_all(2, QueryType::Default, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(c.transport.sent.len(), 1);
+        assert_eq!(c.transport.sent[0].arbitration_id, 0x807f);
+        assert_eq!(
+            responses.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+
+        // only one reply is left
+        let responses = c
+            .query_all(2, QueryType::Default, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].0, 2);
+
+        // a reply which fails to parse is kept with its error
+        let mut c = controller([vec![0xff], vec![0x21, 0x00, 0x0a]]);
+        c.transport.sources = VecDeque::from([4, 5]);
+        let responses = c
+            .query_all(3, QueryType::Default, Duration::from_secs(1))
+            .unwrap();
+        assert!(matches!(responses[0], (4, Err(Error::FrameParse(_)))));
+        assert!(matches!(responses[1], (5, Ok(_))));
+
+        // other errors of the transport are returned
+        c.transport.io_error = true;
+        assert!(matches!(
+            c.query_all(1, QueryType::Default, Duration::from_secs(1)),
+            Err(Error::Transport(_))
+        ));
+    }
+
+    #[test]
+    fn test_play_trajectory() {
+        let ok = vec![0x21, 0x00, 0x0a];
+        let faulted = vec![0x21, 0x00, 0x01, 0x21, 0x0f, 39];
+        let points = [
+            (Duration::ZERO, 0.0),
+            (Duration::from_millis(10), 0.5),
+            (Duration::from_millis(20), 0.5),
+        ];
+        let mut c = controller([ok.clone(), ok.clone(), ok.clone()]);
+        let start = Instant::now();
+        c.play_trajectory(1, &points, Position::default()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        let velocities: Vec<_> = c
+            .transport
+            .sent
+            .iter()
+            .map(|frame| {
+                ResponseFrame::from_bytes(&frame.data)
+                    .unwrap()
+                    .get::<registers::CommandVelocity>()
+                    .unwrap()
+                    .value()
+            })
+            .collect();
+        assert_eq!(velocities, vec![50.0, 0.0, 0.0]);
+
+        let mut c = controller([ok, faulted]);
+        assert!(matches!(
+            c.play_trajectory(1, &points, Position::default()),
+            Err(Error::Faulted(_))
+        ));
+        assert_eq!(c.transport.sent.len(), 2);
+    }
+
+    #[test]
+    fn test_watchdog_guard() {
+        let mut c = controller([]);
+        c.set_watchdog_guard(Some(WatchdogGuard::new(Duration::from_millis(20))));
+        c.send_no_response(1, Position::hold()).unwrap();
+        let guard = c.watchdog_guard().unwrap();
+        assert!(matches!(guard.status(1), WatchdogStatus::Safe(_)));
+        assert_eq!(guard.status(2), WatchdogStatus::Idle);
+
+        c.send_no_response(1, Stop).unwrap();
+        assert_eq!(c.watchdog_guard().unwrap().status(1), WatchdogStatus::Idle);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut c = controller([]);
+        c.set_throttle(Some(Throttle::new(
+            Duration::from_secs(60),
+            ThrottlePolicy::Drop,
+        )));
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert!(matches!(
+            c.send_no_response(1, Position::hold()),
+            Err(Error::Throttled(_))
+        ));
+        // other ids and queries are not throttled
+        c.send_no_response(2, Position::hold()).unwrap();
+        c.send_no_response(1, mode_and_fault()).unwrap();
+        assert_eq!(c.transport.sent.len(), 3);
+
+        c.set_throttle(Some(Throttle::new(
+            Duration::from_millis(5),
+            ThrottlePolicy::Coalesce,
+        )));
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert!(c.send_no_response(1, Position::hold()).is_err());
+        assert!(c.send_no_response(1, Position::default()).is_err());
+        assert!(c.flush_throttled().unwrap().is_empty());
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(c.flush_throttled().unwrap(), vec![1]);
+        assert_eq!(c.transport.sent.len(), 5);
+        assert_eq!(c.transport.sent[4].data, vec![0x01, 0x00, 0x0a]);
+        // a stop is never throttled
+        c.send_no_response(1, Stop).unwrap();
+        assert_eq!(c.throttle().unwrap().pending().count(), 0);
+
+        c.set_throttle(Some(Throttle::new(
+            Duration::from_millis(5),
+            ThrottlePolicy::Block,
+        )));
+        let start = Instant::now();
+        c.send_no_response(1, Position::hold()).unwrap();
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_failed_transmit() {
+        let mut c = controller([]);
+        c.set_throttle(Some(Throttle::new(
+            Duration::from_secs(60),
+            ThrottlePolicy::Drop,
+        )));
+        c.set_watchdog_guard(Some(WatchdogGuard::new(Duration::from_millis(20))));
+        c.transport.fail_transmit = true;
+        assert!(matches!(
+            c.send_no_response(1, Position::hold()),
+            Err(Error::Transport(_))
+        ));
+        assert!(matches!(
+            c.send_with_query(1, Position::hold(), QueryType::Default),
+            Err(Error::Transport(_))
+        ));
+        // a command which was not transmitted is not remembered
+        assert_eq!(c.watchdog_guard().unwrap().status(1), WatchdogStatus::Idle);
+        assert!(matches!(c.repeat_last(1), Err(Error::NoCommand(1))));
+
+        c.transport.fail_transmit = false;
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert_eq!(c.transport.sent.len(), 1);
+    }
+
+    #[test]
+    fn test_register_map_version() {
+        let reply = [&[0x29, 0x82, 0x02][..], &5u32.to_le_bytes()].concat();
+        let mut c = controller([reply]);
+        assert_eq!(c.register_map_version(1).unwrap(), 5);
+        // read with Int32 at the 2 byte varuint address 0x102
+        assert!(c.transport.sent[0].data.starts_with(&[0x19, 0x82, 0x02]));
+    }
+
+    #[test]
+    fn test_prepare_frame() {
+        let mut c = controller([]);
+        c.disable_brs = true;
+        let frame = c.prepare_frame(3, Stop, true).unwrap();
+        assert_eq!(frame.arbitration_id, 0x8003);
+        assert_eq!(frame.brs, Some(false));
+        assert_eq!(frame.data, vec![0x01, 0x00, 0x00]);
+        assert!(c.transport.sent.is_empty());
+
+        c.send_no_response(3, Stop).unwrap();
+        let prepared = c.prepare_frame(3, Stop, false).unwrap();
+        let sent = &c.transport.sent[0];
+        assert_eq!(sent.arbitration_id, prepared.arbitration_id);
+        assert_eq!(sent.data, prepared.data);
+        assert_eq!(sent.brs, prepared.brs);
+    }
+
+    #[test]
+    fn test_max_torque() {
+        let max_torque = |c: &Controller<MockTransport>| {
+            ResponseFrame::from_bytes(&c.transport.sent.last().unwrap().data)
+                .unwrap()
+                .get::<registers::CommandPositionMaxTorque>()
+                .map(|t| t.value())
+        };
+        let mut c = controller([]);
+        c.set_max_torque(1, Some(2.0));
+        assert_eq!(c.max_torque(1), Some(2.0));
+
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert_eq!(max_torque(&c), Some(2.0));
+        c.send_no_response(
+            1,
+            Position {
+                maximum_torque: Some(registers::CommandPositionMaxTorque::write(5.0).unwrap()),
+                ..Position::hold()
+            },
+        )
+        .unwrap();
+        assert_eq!(max_torque(&c), Some(2.0));
+        c.send_no_response(
+            1,
+            Position {
+                maximum_torque: Some(
+                    registers::CommandPositionMaxTorque::write_with_resolution(
+                        1.0,
+                        Resolution::Int16,
+                    )
+                    .unwrap(),
+                ),
+                ..Position::hold()
+            },
+        )
+        .unwrap();
+        assert!((max_torque(&c).unwrap() - 1.0).abs() < 1e-6);
+
+        // other ids and modes are not limited
+        c.send_no_response(2, Position::hold()).unwrap();
+        assert_eq!(max_torque(&c), None);
+        c.send_no_response(1, Stop).unwrap();
+        assert_eq!(max_torque(&c), None);
+        c.set_max_torque(1, None);
+        c.send_no_response(1, Position::hold()).unwrap();
+        assert_eq!(max_torque(&c), None);
+    }
+
+    #[test]
+    fn test_set_output() {
+        let mut c = controller([]);
+        c.set_output_nearest(1, 0.25).unwrap();
+        c.set_output_exact(1, 2.25).unwrap();
+        assert_eq!(
+            c.transport.sent[0].data,
+            [&[0x0d, 0xb0, 0x02][..], &0.25f32.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            c.transport.sent[1].data,
+            [&[0x0d, 0xb1, 0x02][..], &2.25f32.to_le_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn test_write_and_verify() {
+        // CommandPosition (Float)
+        let reply = |position: f32| [&[0x2d, 0x20][..], &position.to_le_bytes()].concat();
+        let mut c = controller([reply(0.5), reply(0.25), vec![]]);
+        assert!(c
+            .write_and_verify::<registers::CommandPosition>(1, 0.5)
+            .unwrap());
+        // the write and the read are sent in one frame
+        assert_eq!(
+            c.transport.sent[0].data,
+            [&[0x0d, 0x20][..], &0.5f32.to_le_bytes(), &[0x1d, 0x20]].concat()
+        );
+        assert!(!c
+            .write_and_verify::<registers::CommandPosition>(1, 0.5)
+            .unwrap());
+        assert!(matches!(
+            c.write_and_verify::<registers::CommandPosition>(1, 0.5),
+            Err(Error::MissingRegister(
+                registers::RegisterAddr::CommandPosition
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_move_and_settle() {
+        // Mode: Position, Fault: Success, Position and Velocity (Float)
+        let reply = |position: f32, velocity: f32| {
+            [
+                &[0x21, 0x00, 0x0a, 0x21, 0x0f, 0x00, 0x2e, 0x01][..],
+                &position.to_le_bytes(),
+                &velocity.to_le_bytes(),
+            ]
+            .concat()
+        };
+        // at the target but still moving, then settled
+        let mut c = controller([reply(0.0, 0.0), reply(0.499, 0.5), reply(0.501, 0.001)]);
+        let response = c
+            .move_and_settle(1, 0.5, 0.01, 0.01, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            response.get::<registers::Position>().unwrap().value(),
+            0.501
+        );
+        assert_eq!(c.transport.sent.len(), 3);
+
+        let mut c = controller([reply(0.0, 0.0)]);
+        assert!(matches!(
+            c.move_and_settle(1, 0.5, 0.01, 0.01, Duration::ZERO),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_send_position_wait_complete() {
+        // Mode: Position, Fault: Success, TrajectoryComplete and MillisecondCounter
+        let reply = |complete: u8, millis: i32| {
+            [
+                &[0x21, 0x00, 0x0a, 0x21, 0x0f, 0x00, 0x21, 0x0b, complete][..],
+                &[0x29, 0x70],
+                &millis.to_le_bytes(),
+            ]
+            .concat()
+        };
+        // the completion of the previous trajectory is ignored until the counter advances
+        let mut c = controller([reply(1, 100), reply(1, 100), reply(0, 101), reply(1, 102)]);
+        let response = c
+            .send_position_wait_complete(1, Position::hold(), Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            response
+                .get::<registers::MillisecondCounter>()
+                .unwrap()
+                .value(),
+            102
+        );
+        assert_eq!(c.transport.sent.len(), 4);
+
+        let mut c = controller([reply(1, 100)]);
+        assert!(matches!(
+            c.send_position_wait_complete(1, Position::hold(), Duration::ZERO),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_query_with() {
+        let mut c = controller([vec![], vec![]]);
+        let _ = c.query_with::<registers::MotorTemperature>(1).unwrap();
+        let _ = c
+            .query_with::<(registers::QCurrent, registers::DCurrent)>(1)
+            .unwrap();
+        let default: FrameBuilder = crate::frame::Query::default().into();
+        let expected =
+            |extra: FrameBuilder| default.clone().merge(extra).build().as_bytes().unwrap();
+        let mut extra = Frame::builder();
+        extra.add(registers::MotorTemperature::read());
+        assert_eq!(c.transport.sent[0].data, expected(extra));
+        let mut extra = Frame::builder();
+        extra
+            .add(registers::QCurrent::read())
+            .add(registers::DCurrent::read());
+        assert_eq!(c.transport.sent[1].data, expected(extra));
+    }
+
+    #[test]
+    fn test_resolution_policy() {
+        let mut c = controller([vec![]]);
+        c.set_resolution_policy(Some(ResolutionPolicy {
+            position: Some(Resolution::Int16),
+            ..Default::default()
+        }));
+        let _ = c.read::<registers::Position>(1);
+        // ReadInt16 of Position
+        assert_eq!(c.transport.sent[0].data, vec![0x15, 0x01]);
+
+        // raw subframes are sent unchanged
+        let mut c = controller([vec![]]);
+        c.set_resolution_policy(Some(ResolutionPolicy {
+            position: Some(Resolution::Int16),
+            ..Default::default()
+        }));
+        let query = Frame::with_builder(|b| {
+            b.add(registers::Position::read_with_resolution(Resolution::Float))
+                .add_raw_subframe(registers::FrameRegisters::WriteInt8, 0x200, 1, &[7]);
+        });
+        let _ = c.query(1, QueryType::Custom(query)).unwrap();
+        assert_eq!(
+            c.transport.sent[0].data,
+            vec![0x15, 0x01, 0x01, 0x80, 0x04, 7]
+        );
+    }
+
+    #[test]
+    fn test_empty_response() {
+        let mut c = controller([vec![]]);
+        let response = c.query(1, QueryType::Default).unwrap();
+        assert!(response.is_empty());
+        assert!(c.transport.replies.is_empty());
+    }
+
+    #[test]
+    fn test_read_write() {
+        // Position as Float
+        let mut c = controller([vec![0x2d, 0x01, 0x00, 0x00, 0x80, 0x3f], vec![]]);
+        assert_eq!(c.read::<registers::Position>(1).unwrap().value(), 1.0);
+        assert_eq!(c.transport.sent[0].data, vec![0x1d, 0x01]);
+        assert!(matches!(
+            c.read::<registers::Velocity>(1),
+            Err(Error::MissingRegister(registers::RegisterAddr::Velocity))
+        ));
+
+        c.write::<registers::CommandTimeout>(1, 0.5).unwrap();
+        assert_eq!(
+            c.transport.sent[2].data,
+            vec![0x0d, 0x27, 0x00, 0x00, 0x00, 0x3f]
+        );
+        assert_eq!(c.transport.sent[2].arbitration_id, 0x0001);
+    }
+
+    #[test]
+    fn test_repeat_last() {
+        let mut c = controller([vec![]]);
+        assert!(matches!(c.repeat_last(1), Err(Error::NoCommand(1))));
+
+        c.send_with_query(1, Position::hold(), QueryType::Default)
+            .unwrap();
+        c.write::<registers::CommandTimeout>(2, 0.5).unwrap();
+        c.repeat_last(1).unwrap();
+        assert!(matches!(c.repeat_last(2), Err(Error::NoCommand(2))));
+
+        // the command is repeated with its query, but without requesting a reply
+        let repeated = c.transport.sent.last().unwrap();
+        assert_eq!(repeated.arbitration_id, 0x0001);
+        assert_eq!(repeated.data, c.transport.sent[0].data);
+    }
+
+    #[test]
+    fn test_pet_watchdog() {
+        let mut c = controller([vec![]]);
+        assert!(matches!(c.pet_watchdog(1), Err(Error::NoCommand(1))));
+
+        c.send_with_query(1, Position::hold(), QueryType::Default)
+            .unwrap();
+        c.pet_watchdog(1).unwrap();
+        let pet = c.transport.sent.last().unwrap();
+        assert_eq!(pet.arbitration_id, 0x0001);
+        assert_eq!(
+            pet.data,
+            FrameBuilder::from(Position::hold())
+                .build()
+                .as_bytes()
+                .unwrap()
+        );
+        assert!(pet.data.len() < c.transport.sent[0].data.len());
+    }
+
+    #[test]
+    fn test_reset_protocol_state() {
+        // Mode: Fault, Fault: OverTemperature
+        let faulted = vec![0x21, 0x00, 0x01, 0x21, 0x0f, 38];
+        let mut c = controller([faulted]);
+        c.set_fault_guard(true);
+        c.send_no_response(1, Position::hold()).unwrap();
+        let _ = c.query(1, QueryType::Default).unwrap();
+
+        // the stale replies are discarded, so the liveness query gets no reply
+        c.transport.replies.extend([vec![0x21, 0x00, 0x0a], vec![]]);
+        assert!(matches!(c.reset_protocol_state(1), Err(Error::NoResponse)));
+        assert!(c.transport.replies.is_empty());
+        assert_eq!(
+            c.transport.sent.last().unwrap().data,
+            vec![0x11, 0x00, 0x11, 0x0f]
+        );
+        // the last command and the fault are forgotten
+        assert!(matches!(c.repeat_last(1), Err(Error::NoCommand(1))));
+        c.send_no_response(1, Position::hold()).unwrap();
+    }
+
+    #[test]
+    fn test_diagnostic_d_command() {
+        let ok = [&[0x41, 0x01, 0x04][..], b"OK\r\n"].concat();
+        let err = [&[0x41, 0x01, 0x07][..], b"ERR x\r\n"].concat();
+        let mut c = controller([ok, err]);
+        assert_eq!(c.diagnostic_d_command(1, "stop").unwrap(), "OK");
+        assert_eq!(
+            c.transport.sent[0].data,
+            [&[0x40, 0x01, 0x07][..], b"d stop\n"].concat()
+        );
+        assert!(matches!(
+            c.diagnostic_d_command(1, "d foo"),
+            Err(Error::Diagnostic(reply)) if reply == "ERR x"
+        ));
+    }
+
+    #[test]
+    fn test_measure_inductance() {
+        let ok = [&[0x41, 0x01, 0x04][..], b"OK\r\n"].concat();
+        let mut c = controller([ok]);
+        c.measure_inductance(1, 0.5, 4).unwrap();
+        assert_eq!(
+            c.transport.sent[0].data,
+            [&[0x40, 0x01, 0x0c][..], b"d ind 0.5 4\n"].concat()
+        );
+    }
+
+    #[test]
+    fn test_clock_trim() {
+        // ClockTrim: -3, Temperature: 25.0
+        let reply = vec![0x21, 0x71, 0xfd, 0x21, 0x0e, 0x19];
+        let mut c = controller([reply]);
+        assert_eq!(
+            c.clock_trim(1).unwrap(),
+            ClockTrimStatus {
+                trim: -3,
+                temperature: 25.0
+            }
+        );
+        c.set_clock_trim(1, 5).unwrap();
+        assert_eq!(c.transport.sent[1].data, vec![0x01, 0x71, 0x05]);
+    }
+
+    #[test]
+    fn test_temperature_fraction() {
+        let reply = [&[0x2d, 0x0e][..], &60.0f32.to_le_bytes()].concat();
+        let config = [&[0x41, 0x01, 0x04][..], b"75\r\n"].concat();
+        let mut c = controller([reply.clone(), config, reply]);
+        assert_eq!(c.temperature_fraction(1, Some(80.0)).unwrap(), 0.75);
+        assert_eq!(c.transport.sent[0].data, vec![0x1d, 0x0e]);
+        // the threshold is read from the config
+        assert_eq!(c.temperature_fraction(1, None).unwrap(), 0.8);
+        assert_eq!(
+            c.transport.sent[1].data,
+            [
+                &[0x40, 0x01, 0x21][..],
+                b"conf get servo.fault_temperature\n"
+            ]
+            .concat()
+        );
+
+        // the threshold is checked before querying the temperature
+        let sent = c.transport.sent.len();
+        for threshold in [0.0, -10.0, f32::NAN] {
+            assert!(matches!(
+                c.temperature_fraction(1, Some(threshold)),
+                Err(Error::InvalidFaultTemperature(_))
+            ));
+        }
+        assert_eq!(c.transport.sent.len(), sent);
+    }
+
+    #[test]
+    fn test_output_source() {
+        let source = [&[0x41, 0x01, 0x03][..], b"1\r\n"].concat();
+        let ok = [&[0x41, 0x01, 0x04][..], b"OK\r\n"].concat();
+        let mut c = controller([source, ok]);
+        assert_eq!(c.output_source(1).unwrap(), 1);
+        c.set_output_source(1, 0).unwrap();
+        assert_eq!(
+            c.transport.sent[2].data,
+            [
+                &[0x40, 0x01, 0x28][..],
+                b"conf set motor_position.output.source 0\n"
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_firmware_version() {
+        let reply = [&[0x41, 0x01, 0x16][..], b"firmware.version 1.2\r\n"].concat();
+        let mut c = controller([reply]);
+        assert_eq!(c.firmware_version(1).unwrap(), "1.2");
+        assert_eq!(
+            c.transport.sent[0].data,
+            [&[0x40, 0x01, 0x11][..], b"tel get firmware\n"].concat()
+        );
+    }
+
+    #[test]
+    fn test_telemetry_stream() {
+        let mode = vec![0x21, 0x00, 0x0a];
+        let mut c = controller([mode.clone(), mode.clone(), mode]);
+        let start = Instant::now();
+        let responses: Vec<_> = c
+            .telemetry_stream(1, QueryType::Default, Duration::from_millis(5))
+            .take(3)
+            .collect();
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert!(responses.iter().all(|r| r.is_ok()));
+        assert_eq!(c.transport.sent.len(), 3);
     }
 }
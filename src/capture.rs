@@ -0,0 +1,248 @@
+//! A compact binary format for capturing raw CAN-FD frames and replaying them later.
+//!
+//! The file starts with the magic bytes `MTFL` and a format version byte. Each frame is then stored as:
+//!
+//! | bytes | content                                            |
+//! |-------|----------------------------------------------------|
+//! | 8     | timestamp, microseconds since the UNIX epoch (LE)  |
+//! | 2     | arbitration id (LE)                                |
+//! | 1     | flags, bit 0: BRS is known, bit 1: BRS was used    |
+//! | 1     | payload length                                     |
+//! | n     | payload                                            |
+
+use crate::registers::CustomRegisters;
+use crate::{FrameParseError, ResponseFrame};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use fdcanusb::CanFdFrame;
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"MTFL";
+const VERSION: u8 = 1;
+
+const BRS_KNOWN: u8 = 0x01;
+const BRS_USED: u8 = 0x02;
+
+/// A frame read back from a capture by [`FrameLogReader`]
+#[derive(Debug)]
+pub struct LoggedFrame {
+    /// The time the frame was written to the log
+    pub timestamp: SystemTime,
+    /// The captured frame
+    pub frame: CanFdFrame,
+}
+
+impl LoggedFrame {
+    /// Parses the payload of the frame as a [`ResponseFrame`]
+    pub fn response(&self) -> Result<ResponseFrame, FrameParseError> {
+        ResponseFrame::from_can_frame(&self.frame, &CustomRegisters::default())
+    }
+}
+
+/// Writes [`CanFdFrame`]s to a capture.
+///
+/// ```rust
+/// # use moteus::{FrameLog, FrameLogReader};
+/// # use fdcanusb::CanFdFrame;
+/// # fn main() -> std::io::Result<()> {
+/// let mut log = FrameLog::new(Vec::new())?;
+/// log.write(&CanFdFrame {
+///     arbitration_id: 0x0100,
+///     data: vec![0x21, 0x00, 0x0a],
+///     ..Default::default()
+/// })?;
+/// let bytes = log.into_inner();
+///
+/// let frames = FrameLogReader::new(bytes.as_slice())?.collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(frames[0].frame.arbitration_id, 0x0100);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameLog<W> {
+    writer: W,
+}
+
+impl<W: Write> FrameLog<W> {
+    /// Creates a new capture, writing the header to `writer`
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(VERSION)?;
+        Ok(Self { writer })
+    }
+
+    /// Writes a frame with the current time as its timestamp
+    pub fn write(&mut self, frame: &CanFdFrame) -> io::Result<()> {
+        self.write_with_timestamp(frame, SystemTime::now())
+    }
+
+    /// Writes a frame with the given timestamp
+    pub fn write_with_timestamp(
+        &mut self,
+        frame: &CanFdFrame,
+        timestamp: SystemTime,
+    ) -> io::Result<()> {
+        let len = u8::try_from(frame.data.len())
+            .ok()
+            .filter(|len| *len <= 64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "payload over 64 bytes"))?;
+        let micros = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let flags = match frame.brs {
+            Some(true) => BRS_KNOWN | BRS_USED,
+            Some(false) => BRS_KNOWN,
+            None => 0,
+        };
+        self.writer.write_u64::<LE>(micros)?;
+        self.writer.write_u16::<LE>(frame.arbitration_id)?;
+        self.writer.write_u8(flags)?;
+        self.writer.write_u8(len)?;
+        self.writer.write_all(&frame.data)
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the frames of a capture written by [`FrameLog`], yielding each [`LoggedFrame`].
+pub struct FrameLogReader<R> {
+    reader: R,
+}
+
+impl<R: Read> FrameLogReader<R> {
+    /// Opens a capture, checking the header
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a frame capture",
+            ));
+        }
+        let version = reader.read_u8()?;
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported capture version {version}"),
+            ));
+        }
+        Ok(Self { reader })
+    }
+
+    fn read_frame(&mut self, first: u8) -> io::Result<LoggedFrame> {
+        let mut timestamp = [0; 8];
+        timestamp[0] = first;
+        self.reader.read_exact(&mut timestamp[1..])?;
+        let micros = u64::from_le_bytes(timestamp);
+        let arbitration_id = self.reader.read_u16::<LE>()?;
+        let flags = self.reader.read_u8()?;
+        let len = self.reader.read_u8()?;
+        let mut data = vec![0; len as usize];
+        self.reader.read_exact(&mut data)?;
+        Ok(LoggedFrame {
+            timestamp: UNIX_EPOCH + Duration::from_micros(micros),
+            frame: CanFdFrame {
+                arbitration_id,
+                data,
+                brs: (flags & BRS_KNOWN != 0).then_some(flags & BRS_USED != 0),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+impl<R: Read> Iterator for FrameLogReader<R> {
+    type Item = io::Result<LoggedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the end of the capture is only valid between frames
+        let mut first = [0; 1];
+        match self.reader.read(&mut first) {
+            Ok(0) => None,
+            Ok(_) => Some(self.read_frame(first[0])),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::registers::{Mode, Modes};
+
+    #[test]
+    fn test_round_trip() {
+        let timestamp = UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456);
+        let frames = [
+            CanFdFrame {
+                arbitration_id: 0x8001,
+                data: vec![0x01, 0x00, 0x0a],
+                brs: Some(true),
+                ..Default::default()
+            },
+            CanFdFrame {
+                arbitration_id: 0x0100,
+                data: vec![0x21, 0x00, 0x0a],
+                brs: None,
+                ..Default::default()
+            },
+        ];
+        let mut log = FrameLog::new(Vec::new()).unwrap();
+        for frame in &frames {
+            log.write_with_timestamp(frame, timestamp).unwrap();
+        }
+        let bytes = log.into_inner();
+        assert_eq!(bytes.len(), 5 + 2 * (12 + 3));
+
+        let read: Vec<_> = FrameLogReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        for (read, frame) in read.iter().zip(&frames) {
+            assert_eq!(read.timestamp, timestamp);
+            assert_eq!(read.frame.arbitration_id, frame.arbitration_id);
+            assert_eq!(read.frame.data, frame.data);
+            assert_eq!(read.frame.brs, frame.brs);
+        }
+        assert_eq!(
+            read[1].response().unwrap().get::<Mode>().unwrap().value(),
+            Modes::Position
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(FrameLogReader::new(&b"MTFX\x01"[..]).is_err());
+        assert!(FrameLogReader::new(&b"MTFL\x02"[..]).is_err());
+
+        let mut log = FrameLog::new(Vec::new()).unwrap();
+        log.write(&CanFdFrame {
+            data: vec![0x50; 8],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut bytes = log.into_inner();
+        let _ = bytes.pop();
+        let mut reader = FrameLogReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.next().unwrap().is_err());
+
+        let mut log = FrameLog::new(Vec::new()).unwrap();
+        assert!(log
+            .write(&CanFdFrame {
+                data: vec![0; 65],
+                ..Default::default()
+            })
+            .is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! A portable description of a frame, which can be (de)serialized with [serde](https://serde.rs/).
+//!
+//! Requires the `serde` feature.
+//!
+//! Registers are identified by name (as in [`crate::registers::Register::NAME`]) and values are given in the
+//! same units used by [`crate::registers::Writeable::write`], so a frame can be defined without recompiling.
+//!
+//! ```rust
+//! # use moteus::description::FrameDescription;
+//! # use moteus::FrameBuilder;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let json = r#"[
+//!     { "register": "Mode", "value": 10 },
+//!     { "register": "CommandPosition", "value": 0.5, "resolution": "Int16" },
+//!     { "register": "Position", "resolution": "Float" }
+//! ]"#;
+//! let description: FrameDescription = serde_json::from_str(json)?;
+//! let builder = FrameBuilder::try_from(&description)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::protocol::registers::RegisterData;
+use crate::registers::RegisterAddr;
+use crate::{Frame, FrameBuilder, RegisterError, Resolution};
+use serde::{Deserialize, Serialize};
+
+/// A list of registers to read or write, which can be converted to and from a [`FrameBuilder`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FrameDescription(pub Vec<RegisterDescription>);
+
+/// A single register within a [`FrameDescription`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisterDescription {
+    /// The name of the register, as in [`crate::registers::Register::NAME`]
+    pub register: String,
+    /// The value to write to the register, or [`None`] if the register is read.
+    /// Enum registers (such as `Mode`) use the numeric value of the variant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// The [`Resolution`] to use, or [`None`] for the default resolution of the register.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+}
+
+impl TryFrom<&FrameDescription> for FrameBuilder {
+    type Error = RegisterError;
+
+    fn try_from(description: &FrameDescription) -> Result<Self, Self::Error> {
+        let mut builder = Frame::builder();
+        for reg in &description.0 {
+            let address = RegisterAddr::from_name(&reg.register)
+                .ok_or_else(|| RegisterError::InvalidName(reg.register.clone()))?;
+            let resolution = reg.resolution.unwrap_or(address.default_resolution());
+            let data = match reg.value {
+                Some(value) => address.write_f64(value, resolution)?,
+                None => RegisterData {
+                    address,
+                    resolution,
+                    data: None,
+                },
+            };
+            builder.add(data);
+        }
+        Ok(builder)
+    }
+}
+
+impl TryFrom<FrameDescription> for FrameBuilder {
+    type Error = RegisterError;
+
+    fn try_from(description: FrameDescription) -> Result<Self, Self::Error> {
+        FrameBuilder::try_from(&description)
+    }
+}
+
+impl TryFrom<&FrameBuilder> for FrameDescription {
+    type Error = RegisterError;
+
+    /// The registers are ordered by address. Raw subframes can't be described by name, so they are not included,
+    /// serialize the [`FrameBuilder`] itself to keep them.
+    fn try_from(builder: &FrameBuilder) -> Result<Self, Self::Error> {
+        let mut registers: Vec<&RegisterData> = builder.registers().collect();
+        registers.sort_by_key(|reg| reg.address as u16);
+        let registers = registers
+            .into_iter()
+            .map(|reg| {
+                let value = match reg.data {
+                    Some(_) => Some(reg.address.read_f64(reg)?),
+                    None => None,
+                };
+                Ok(RegisterDescription {
+                    register: reg.address.name().to_string(),
+                    value,
+                    resolution: Some(reg.resolution),
+                })
+            })
+            .collect::<Result<Vec<_>, RegisterError>>()?;
+        Ok(FrameDescription(registers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::registers::{self, Readable, Writeable};
+
+    #[test]
+    fn test_from_json() {
+        let json = r#"[
+            { "register": "Mode", "value": 10 },
+            { "register": "CommandPosition", "value": 0.5, "resolution": "Int16" },
+            { "register": "Position", "resolution": "Float" }
+        ]"#;
+        let description: FrameDescription = serde_json::from_str(json).unwrap();
+        let builder = FrameBuilder::try_from(&description).unwrap();
+
+        let expected = Frame::with_builder(|b| {
+            b.add(registers::Mode::write(registers::Modes::Position).unwrap())
+                .add(
+                    registers::CommandPosition::write_with_resolution(0.5, Resolution::Int16)
+                        .unwrap(),
+                )
+                .add(registers::Position::read_with_resolution(Resolution::Float));
+        });
+        assert_eq!(builder, expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let builder = Frame::with_builder(|b| {
+            b.add(registers::Mode::write(registers::Modes::Position).unwrap())
+                .add(registers::CommandVelocity::write(-1.5).unwrap())
+                .add(registers::Fault::read());
+        });
+        let description = FrameDescription::try_from(&builder).unwrap();
+        assert_eq!(description.0[0].register, "Mode");
+        assert_eq!(description.0[0].value, Some(10.0));
+
+        let json = serde_json::to_string(&description).unwrap();
+        let description: FrameDescription = serde_json::from_str(&json).unwrap();
+        assert_eq!(FrameBuilder::try_from(description).unwrap(), builder);
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        let description = FrameDescription(vec![RegisterDescription {
+            register: "NotARegister".to_string(),
+            value: None,
+            resolution: None,
+        }]);
+        assert!(matches!(
+            FrameBuilder::try_from(&description),
+            Err(RegisterError::InvalidName(_))
+        ));
+    }
+}
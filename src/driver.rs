@@ -0,0 +1,217 @@
+//! Decoding the gate driver faults of a controller.
+
+use crate::registers::{self, Faults, Readable};
+use crate::{Frame, FrameBuilder, ResponseFrame};
+use std::fmt;
+
+/// A condition reported by the gate driver in [`registers::DriverFault1`] or [`registers::DriverFault2`].
+///
+/// The registers hold the fault status 1 and VGS status 2 registers of the DRV8353 gate driver used by the
+/// moteus r4 and n1. Boards with another gate driver may report different bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverCondition {
+    /// A MOSFET drain-source voltage exceeded the overcurrent threshold, `VDS_OCP`
+    VdsOvercurrent,
+    /// A gate drive fault, `GDF`
+    GateDriveFault,
+    /// The supply voltage fell below the undervoltage lockout, `UVLO`
+    Undervoltage,
+    /// The gate driver shut down from overtemperature, `OTSD`
+    OvertemperatureShutdown,
+    /// The drain-source voltage of a MOSFET exceeded the overcurrent threshold, `VDS_HA` to `VDS_LC`
+    VdsFault {
+        /// The phase of the MOSFET, `'A'`, `'B'` or `'C'`
+        phase: char,
+        /// Whether the MOSFET is on the high side of the phase
+        high_side: bool,
+    },
+    /// The current sense amplifier of a phase detected an overcurrent, `SA_OC` to `SC_OC`
+    SenseOvercurrent {
+        /// The phase of the amplifier, `'A'`, `'B'` or `'C'`
+        phase: char,
+    },
+    /// The gate driver is approaching its overtemperature shutdown, `OTW`
+    OvertemperatureWarning,
+    /// The gate drive supply fell below its undervoltage threshold, `GDUV`
+    GateDriveUndervoltage,
+    /// The gate-source voltage of a MOSFET did not reach its target, `VGS_HA` to `VGS_LC`
+    VgsFault {
+        /// The phase of the MOSFET, `'A'`, `'B'` or `'C'`
+        phase: char,
+        /// Whether the MOSFET is on the high side of the phase
+        high_side: bool,
+    },
+}
+
+impl DriverCondition {
+    /// Decodes the conditions set in the values of [`registers::DriverFault1`] and [`registers::DriverFault2`]
+    pub fn decode(driver_fault1: u32, driver_fault2: u32) -> Vec<DriverCondition> {
+        let set1 = |bit: u32| driver_fault1 & (1 << bit) != 0;
+        let set2 = |bit: u32| driver_fault2 & (1 << bit) != 0;
+        let mut conditions = Vec::new();
+        for (bit, condition) in [
+            (9, DriverCondition::VdsOvercurrent),
+            (8, DriverCondition::GateDriveFault),
+            (7, DriverCondition::Undervoltage),
+            (6, DriverCondition::OvertemperatureShutdown),
+        ] {
+            if set1(bit) {
+                conditions.push(condition);
+            }
+        }
+        // bits 5 to 0 are the high then low side of phases A, B and C
+        let mosfets = || {
+            (0..6u32).map(|i| {
+                let phase = ['A', 'B', 'C'][i as usize / 2];
+                (5 - i, phase, i % 2 == 0)
+            })
+        };
+        for (bit, phase, high_side) in mosfets() {
+            if set1(bit) {
+                conditions.push(DriverCondition::VdsFault { phase, high_side });
+            }
+        }
+        for (bit, phase) in [(10, 'A'), (9, 'B'), (8, 'C')] {
+            if set2(bit) {
+                conditions.push(DriverCondition::SenseOvercurrent { phase });
+            }
+        }
+        if set2(7) {
+            conditions.push(DriverCondition::OvertemperatureWarning);
+        }
+        if set2(6) {
+            conditions.push(DriverCondition::GateDriveUndervoltage);
+        }
+        for (bit, phase, high_side) in mosfets() {
+            if set2(bit) {
+                conditions.push(DriverCondition::VgsFault { phase, high_side });
+            }
+        }
+        conditions
+    }
+}
+
+impl fmt::Display for DriverCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |high_side: bool| if high_side { "high" } else { "low" };
+        match self {
+            DriverCondition::VdsOvercurrent => write!(f, "VDS overcurrent"),
+            DriverCondition::GateDriveFault => write!(f, "gate drive fault"),
+            DriverCondition::Undervoltage => write!(f, "undervoltage lockout"),
+            DriverCondition::OvertemperatureShutdown => write!(f, "overtemperature shutdown"),
+            DriverCondition::VdsFault { phase, high_side } => {
+                write!(
+                    f,
+                    "VDS fault on the {} side of phase {phase}",
+                    side(*high_side)
+                )
+            }
+            DriverCondition::SenseOvercurrent { phase } => {
+                write!(f, "sense amplifier overcurrent on phase {phase}")
+            }
+            DriverCondition::OvertemperatureWarning => write!(f, "overtemperature warning"),
+            DriverCondition::GateDriveUndervoltage => write!(f, "gate drive undervoltage"),
+            DriverCondition::VgsFault { phase, high_side } => {
+                write!(
+                    f,
+                    "VGS fault on the {} side of phase {phase}",
+                    side(*high_side)
+                )
+            }
+        }
+    }
+}
+
+/// The fault of a controller with the state of its gate driver, created by [`crate::Controller::driver_fault_report`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriverFaultReport {
+    /// The fault reported by the controller
+    pub fault: Faults,
+    /// The raw value of [`registers::DriverFault1`]
+    pub driver_fault1: u32,
+    /// The raw value of [`registers::DriverFault2`]
+    pub driver_fault2: u32,
+}
+
+impl DriverFaultReport {
+    /// Returns a frame reading the registers needed for the report
+    pub fn query() -> FrameBuilder {
+        Frame::with_builder(|b| {
+            b.add(registers::Fault::read())
+                .add(registers::DriverFault1::read())
+                .add(registers::DriverFault2::read());
+        })
+    }
+
+    /// Builds the report from a response to [`DriverFaultReport::query`].
+    /// If any of the registers is not found [`None`] is returned.
+    pub fn from_response(response: &ResponseFrame) -> Option<Self> {
+        Some(Self {
+            fault: response.get::<registers::Fault>()?.value(),
+            driver_fault1: response.get::<registers::DriverFault1>()?.value(),
+            driver_fault2: response.get::<registers::DriverFault2>()?.value(),
+        })
+    }
+
+    /// Returns the conditions reported by the gate driver, see [`DriverCondition::decode`]
+    pub fn conditions(&self) -> Vec<DriverCondition> {
+        DriverCondition::decode(self.driver_fault1, self.driver_fault2)
+    }
+}
+
+impl fmt::Display for DriverFaultReport {
+    /// Formats the fault followed by the driver conditions, such as
+    /// `MotorDriverFault: VDS overcurrent, VDS fault on the high side of phase A`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.fault)?;
+        for (i, condition) in self.conditions().iter().enumerate() {
+            let separator = if i == 0 { ": " } else { ", " };
+            write!(f, "{separator}{condition}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // VDS_OCP and VDS_HA, then SB_OC and VGS_LC
+        let conditions = DriverCondition::decode(0b10_0010_0000, 0b10_0000_0001);
+        assert_eq!(
+            conditions,
+            vec![
+                DriverCondition::VdsOvercurrent,
+                DriverCondition::VdsFault {
+                    phase: 'A',
+                    high_side: true
+                },
+                DriverCondition::SenseOvercurrent { phase: 'B' },
+                DriverCondition::VgsFault {
+                    phase: 'C',
+                    high_side: false
+                },
+            ]
+        );
+        // the summary FAULT bit alone is not a condition
+        assert!(DriverCondition::decode(1 << 10, 0).is_empty());
+    }
+
+    #[test]
+    fn test_report() {
+        // Fault: MotorDriverFault, DriverFault1: VDS_OCP and VDS_LB, DriverFault2: 0
+        let mut buf = vec![0x21, 0x0f, 33, 0x2a, 0xc0, 0x02];
+        buf.extend(0b10_0000_0100u32.to_le_bytes());
+        buf.extend(0u32.to_le_bytes());
+        let report =
+            DriverFaultReport::from_response(&ResponseFrame::from_bytes(&buf).unwrap()).unwrap();
+        assert_eq!(report.fault, Faults::MotorDriverFault);
+        assert_eq!(
+            report.to_string(),
+            "MotorDriverFault: VDS overcurrent, VDS fault on the low side of phase B"
+        );
+    }
+}
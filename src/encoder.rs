@@ -0,0 +1,214 @@
+//! Interpreting the health of the encoders of a controller.
+
+use crate::registers::{self, Faults, Readable};
+use crate::{Frame, FrameBuilder, Resolution, ResponseFrame};
+
+/// The number of encoders reported by the moteus
+pub const ENCODER_COUNT: usize = 3;
+
+/// The state of a single encoder, as reported in [`EncoderReadings`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderStatus {
+    /// Whether the bit of the encoder is set in [`registers::EncoderValidity`]
+    pub valid: bool,
+    /// The position of the encoder in revolutions, if it was reported
+    pub position: Option<f32>,
+    /// The velocity of the encoder in revolutions per second, if it was reported
+    pub velocity: Option<f32>,
+}
+
+impl EncoderStatus {
+    /// Returns `true` if the encoder is valid and reports a finite position and velocity
+    pub fn is_healthy(&self) -> bool {
+        self.valid
+            && self.position.is_some_and(f32::is_finite)
+            && self.velocity.is_some_and(f32::is_finite)
+    }
+}
+
+/// The validity, position and velocity of each encoder of a controller, read in a single query by
+/// [`crate::Controller::read_encoders`].
+///
+/// This is the encoder half of [`EncoderDiagnostics`], without the [`registers::Fault`], for correlating the
+/// encoders while commissioning a controller. Positions and velocities are read at [`Resolution::Float`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderReadings {
+    /// The status of encoders 0, 1 and 2
+    pub encoders: [EncoderStatus; ENCODER_COUNT],
+}
+
+impl EncoderReadings {
+    /// Returns a frame reading [`registers::EncoderValidity`] and the position and velocity of each encoder
+    pub fn query() -> FrameBuilder {
+        Frame::with_builder(|b| {
+            b.add(registers::EncoderValidity::read())
+                .add(registers::Encoder0position::read_with_resolution(
+                    Resolution::Float,
+                ))
+                .add(registers::Encoder0velocity::read_with_resolution(
+                    Resolution::Float,
+                ))
+                .add(registers::Encoder1position::read_with_resolution(
+                    Resolution::Float,
+                ))
+                .add(registers::Encoder1velocity::read_with_resolution(
+                    Resolution::Float,
+                ))
+                .add(registers::Encoder2position::read_with_resolution(
+                    Resolution::Float,
+                ))
+                .add(registers::Encoder2velocity::read_with_resolution(
+                    Resolution::Float,
+                ));
+        })
+    }
+
+    /// Builds the readings from a response to [`EncoderReadings::query`].
+    /// If the [`registers::EncoderValidity`] is not found [`None`] is returned,
+    /// missing positions and velocities are reported as [`None`].
+    pub fn from_response(response: &ResponseFrame) -> Option<Self> {
+        let validity = response.get::<registers::EncoderValidity>()?.value() as u8;
+        let positions = [
+            response
+                .get::<registers::Encoder0position>()
+                .map(|r| r.value()),
+            response
+                .get::<registers::Encoder1position>()
+                .map(|r| r.value()),
+            response
+                .get::<registers::Encoder2position>()
+                .map(|r| r.value()),
+        ];
+        let velocities = [
+            response
+                .get::<registers::Encoder0velocity>()
+                .map(|r| r.value()),
+            response
+                .get::<registers::Encoder1velocity>()
+                .map(|r| r.value()),
+            response
+                .get::<registers::Encoder2velocity>()
+                .map(|r| r.value()),
+        ];
+        let encoders = std::array::from_fn(|i| EncoderStatus {
+            valid: validity & (1 << i) != 0,
+            position: positions[i],
+            velocity: velocities[i],
+        });
+        Some(Self { encoders })
+    }
+
+    /// Returns the indices of the encoders set in [`registers::EncoderValidity`]
+    pub fn valid(&self) -> Vec<usize> {
+        (0..ENCODER_COUNT)
+            .filter(|i| self.encoders[*i].valid)
+            .collect()
+    }
+}
+
+/// A report of the health of each encoder of a controller, created by [`crate::Controller::encoder_diagnostics`].
+///
+/// Encoders that are not configured on the controller are also reported as invalid,
+/// so compare [`EncoderDiagnostics::unhealthy`] against the encoders in use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderDiagnostics {
+    /// The status of encoders 0, 1 and 2
+    pub encoders: [EncoderStatus; ENCODER_COUNT],
+    /// The fault reported by the controller
+    pub fault: Faults,
+}
+
+impl EncoderDiagnostics {
+    /// Returns a frame reading the registers needed for the report, the [`registers::Fault`] and [`EncoderReadings::query`]
+    pub fn query() -> FrameBuilder {
+        Frame::with_builder(|b| {
+            b.add(registers::Fault::read())
+                .merge_from(&EncoderReadings::query());
+        })
+    }
+
+    /// Builds the report from a response to [`EncoderDiagnostics::query`].
+    /// If the [`registers::Fault`] or [`registers::EncoderValidity`] is not found [`None`] is returned.
+    pub fn from_response(response: &ResponseFrame) -> Option<Self> {
+        let fault = response.get::<registers::Fault>()?.value();
+        let EncoderReadings { encoders } = EncoderReadings::from_response(response)?;
+        Some(Self { encoders, fault })
+    }
+
+    /// Returns the indices of the encoders which are not healthy
+    pub fn unhealthy(&self) -> Vec<usize> {
+        (0..ENCODER_COUNT)
+            .filter(|i| !self.encoders[*i].is_healthy())
+            .collect()
+    }
+
+    /// Returns `true` if the controller reports [`Faults::EncoderFault`]
+    pub fn is_encoder_fault(&self) -> bool {
+        self.fault == Faults::EncoderFault
+    }
+
+    /// Returns the encoders that likely caused a [`Faults::EncoderFault`], among the encoders in `used`.
+    /// Returns an empty list if the controller does not report an encoder fault.
+    pub fn suspects(&self, used: &[usize]) -> Vec<usize> {
+        if !self.is_encoder_fault() {
+            return Vec::new();
+        }
+        self.unhealthy()
+            .into_iter()
+            .filter(|i| used.contains(i))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn response(fault: u8, validity: u8, encoder1: f32) -> ResponseFrame {
+        let mut buf = vec![0x21, 0x0f, fault, 0x21, 0x58, validity, 0x2e, 0x50];
+        buf.extend(0.5f32.to_le_bytes());
+        buf.extend(0.0f32.to_le_bytes());
+        buf.extend([0x2e, 0x52]);
+        buf.extend(encoder1.to_le_bytes());
+        buf.extend(0.0f32.to_le_bytes());
+        ResponseFrame::from_bytes(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_encoder_fault() {
+        // encoder 0 valid, encoder 1 invalid and encoder 2 not reported
+        let report = EncoderDiagnostics::from_response(&response(35, 0b001, f32::NAN)).unwrap();
+        assert!(report.is_encoder_fault());
+        assert!(report.encoders[0].is_healthy());
+        assert!(!report.encoders[1].valid);
+        assert_eq!(report.encoders[2].position, None);
+        assert_eq!(report.unhealthy(), vec![1, 2]);
+        assert_eq!(report.suspects(&[0, 1]), vec![1]);
+    }
+
+    #[test]
+    fn test_readings() {
+        let readings = EncoderReadings::from_response(&response(0, 0b011, 0.25)).unwrap();
+        assert_eq!(readings.valid(), vec![0, 1]);
+        assert_eq!(readings.encoders[0].position, Some(0.5));
+        assert_eq!(readings.encoders[1].position, Some(0.25));
+        assert_eq!(readings.encoders[2].velocity, None);
+        // the fault is not needed
+        let buf = [0x21, 0x58, 0b100];
+        let readings = EncoderReadings::from_response(&ResponseFrame::from_bytes(&buf).unwrap());
+        assert_eq!(readings.unwrap().valid(), vec![2]);
+    }
+
+    #[test]
+    fn test_no_fault() {
+        let report = EncoderDiagnostics::from_response(&response(0, 0b011, 0.25)).unwrap();
+        assert!(!report.is_encoder_fault());
+        assert_eq!(report.unhealthy(), vec![2]);
+        assert!(report.suspects(&[0, 1, 2]).is_empty());
+        assert_eq!(
+            EncoderDiagnostics::from_response(&ResponseFrame::from_bytes(&[]).unwrap()),
+            None
+        );
+    }
+}
@@ -3,13 +3,21 @@ use thiserror::Error;
 /// Errors that can occur when interacting with the Moteus.
 #[derive(Error, Debug)]
 pub enum Error<T> {
-    /// Transfer errors occur when reading or writing frames to the fdcanusb.
+    /// Transport errors occur when reading or writing frames with the [`crate::Controller`] transport, e.g. the fdcanusb.
     #[error(transparent)]
     Transport(T),
     /// Data overflow errors occur when the data is > 64 bytes.
     /// 64 bytes is the max frame length in the CAN FD protocol.
     #[error("data overflow error: {0}")]
     InvalidFrameLength(usize),
+    /// The frame is larger than the limit set with [`crate::Controller::set_max_payload`].
+    #[error("payload of {len} bytes exceeds the maximum of {max} bytes")]
+    PayloadTooLarge {
+        /// The length of the frame
+        len: usize,
+        /// The maximum payload size
+        max: usize,
+    },
     /// Frame errors occur when creating frames from an invalid combination of registers.
     #[error("frame error: {0}")]
     Frame(#[from] FrameError),
@@ -22,6 +30,28 @@ pub enum Error<T> {
     /// No response was received.
     #[error("no response")]
     NoResponse,
+    /// The controller did not reach the expected state in time.
+    #[error("timed out")]
+    Timeout,
+    /// The command was refused because the controller last reported a fault, see [`crate::Controller::set_fault_guard`].
+    #[error("controller is faulted: {0:?}{}", .0.hint().map(|hint| format!(", {hint}")).unwrap_or_default())]
+    Faulted(crate::registers::Faults),
+    /// The register was not found in the response, see [`crate::Controller::read`].
+    #[error("register missing from response: {0:?}")]
+    MissingRegister(crate::registers::RegisterAddr),
+    /// No command has been sent to the controller with this id, see [`crate::Controller::repeat_last`].
+    #[error("no command has been sent to {0}")]
+    NoCommand(u8),
+    /// The command was sent before the interval of the [`crate::Throttle`] had passed, and was not sent.
+    /// Holds the time remaining until the controller can be commanded again.
+    #[error("command throttled for {0:?}")]
+    Throttled(std::time::Duration),
+    /// The diagnostic stream replied with an error or an unexpected reply.
+    #[error("diagnostic error: {0}")]
+    Diagnostic(String),
+    /// The fault temperature used by [`crate::Controller::temperature_fraction`] is not finite and positive.
+    #[error("invalid fault temperature: {0}")]
+    InvalidFaultTemperature(f32),
 }
 
 /// Errors that can occur when creating frames from multiple subframes.
@@ -60,21 +90,69 @@ pub enum FrameParseError {
     /// Subframes are collections of registers. Errors can occur when parsing each register.
     #[error("error parsing data into register: {0}")]
     RegisterError(#[from] RegisterError),
+    /// The frame ended before the subframe was complete.
+    #[error("frame truncated")]
+    Truncated,
+    /// The length of a subframe does not fit in a `u8`.
+    #[error("subframe length {0} out of range")]
+    LengthOutOfRange(u32),
+    /// A register address of a subframe is past the end of the 16-bit address space.
+    #[error("register address {0:#x} out of range")]
+    AddressOutOfRange(u32),
+    /// The diagnostic stream reply was for a different channel or its length exceeds the frame.
+    #[error("invalid diagnostic reply")]
+    InvalidDiagnosticReply,
+    /// The frame does not strictly conform to the protocol, see [`crate::ResponseFrame::from_bytes_strict`].
+    #[error("non-conforming frame: {0}")]
+    NonConforming(&'static str),
+    /// Parsing a subframe of a [`crate::ResponseFrame`] failed. Use [`FrameParseError::root`] to get the cause.
+    #[error("subframe {index} at byte {offset}: {source}")]
+    Subframe {
+        /// The index of the subframe in the frame, counting NOP bytes as subframes
+        index: usize,
+        /// The byte offset of the start of the subframe in the frame
+        offset: usize,
+        /// The error parsing the subframe
+        source: Box<FrameParseError>,
+    },
+}
+
+impl FrameParseError {
+    /// Returns the underlying error, without the location added by [`FrameParseError::Subframe`]
+    pub fn root(&self) -> &FrameParseError {
+        match self {
+            FrameParseError::Subframe { source, .. } => source.root(),
+            e => e,
+        }
+    }
 }
 
 /// Errors that can occur when writing and/or parsing registers
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum RegisterError {
-    /// Returned when the value is too large to fit in the register
+    /// Returned when the value is too large to fit in the register at any resolution
     #[error("value too large")]
     Overflow,
+    /// Returned when the value is too large for the requested resolution, but fits in a larger one
+    #[error("{register} value does not fit in {resolution:?}, the register requires {required:?}")]
+    ResolutionTooSmall {
+        /// The name of the register, as in [`crate::registers::Register::NAME`]
+        register: &'static str,
+        /// The requested resolution
+        resolution: crate::Resolution,
+        /// The smallest resolution which holds the value
+        required: crate::Resolution,
+    },
     /// Returned when data is tried into a type that is not valid.
     #[error("invalid data")]
     InvalidData,
     /// Returned when the parsed address of a register is invalid. All valid addresses are defined in the [`crate::registers::RegisterAddr`] enum
-    #[error("invalid address")]
-    InvalidAddress,
+    #[error("invalid address: {0:#05x}")]
+    InvalidAddress(u16),
+    /// Returned when a negative value is written to an unsigned register, such as [`crate::registers::SerialNumber`]
+    #[error("negative value for unsigned register")]
+    NegativeValue,
     /// Returned when a float is tried to be written to a register that only accepts integers
     #[error("float as int")]
     IntAsFloat,
@@ -86,4 +164,7 @@ pub enum RegisterError {
     /// Returned when writing is attempted with a register instance that doesn't have any data.
     #[error("cannot write register with no data")]
     NoData,
+    /// Returned when a register name doesn't match any [`crate::registers::Register::NAME`]
+    #[error("invalid register name: {0}")]
+    InvalidName(String),
 }
@@ -6,6 +6,11 @@ use crate::registers::{Read, Readable, Write, Writeable};
 use crate::{registers, Resolution};
 
 /// Sets the mode to `registers::Modes::Stopped`.
+///
+/// This is the same frame as `make_stop` of the reference Python library, which only writes the
+/// [`registers::Mode`]. Entering the stopped mode clears any fault and ends the current command, so no
+/// other registers need to be reset at startup. Command registers such as the stop position are only used
+/// while in position mode, and are written again with the next [`Position`] command.
 #[derive(Debug, Default, Clone)]
 pub struct Stop;
 
@@ -23,6 +28,7 @@ impl From<Stop> for FrameBuilder {
 ///
 /// Additionally, some associated methods are provided. See:
 ///  - [`Position::hold`]
+///  - [`Position::move_to_with_stop`]
 #[derive(Debug, Default, Clone)]
 pub struct Position {
     /// The `position` field is used to set the [`registers::CommandPosition`] of the motor.
@@ -38,6 +44,10 @@ pub struct Position {
     /// The `maximum_torque` field is used to set the [`registers::CommandPositionMaxTorque`] of the motor.
     pub maximum_torque: Option<Write<registers::CommandPositionMaxTorque>>,
     /// The `stop_position` field is used to set the [`registers::CommandStopPosition`] of the motor.
+    ///
+    /// The stop position is deprecated in the firmware. Combined with the `velocity_limit` or
+    /// `acceleration_limit` it causes [`registers::Faults::StopPositionDeprecated`], so to move to a target
+    /// within limits command the target as the `position` instead.
     pub stop_position: Option<Write<registers::CommandStopPosition>>,
     /// The `watchdog_timeout` field is used to set the [`registers::CommandTimeout`] of the motor.
     pub watchdog_timeout: Option<Write<registers::CommandTimeout>>,
@@ -57,6 +67,64 @@ impl Position {
             ..Self::default()
         }
     }
+
+    /// Sets the [`registers::CommandPosition`] to `target` and the [`registers::CommandStopPosition`] to `stop_at`,
+    /// both in revolutions.
+    ///
+    /// The stop position bounds the move: once the control position reaches `stop_at` the controller holds it,
+    /// even if a nonzero velocity is still commanded. This makes it safe to command a velocity towards a limit,
+    /// such as `Position::move_to_with_stop(f32::NAN, 0.5)` with a velocity set to run until 0.5 revolutions.
+    /// Don't set a velocity or acceleration limit on the command, which faults with
+    /// [`registers::Faults::StopPositionDeprecated`], see [`Position::stop_position`].
+    ///
+    /// ```rust
+    /// # use moteus::frame::Position;
+    /// # use moteus::registers::{CommandVelocity, Writeable};
+    /// let command = Position {
+    ///     velocity: Some(CommandVelocity::write(0.2)?),
+    ///     ..Position::move_to_with_stop(f32::NAN, 0.5)
+    /// };
+    /// # Ok::<(), moteus::RegisterError>(())
+    /// ```
+    pub fn move_to_with_stop(target: f32, stop_at: f32) -> Self {
+        Self {
+            position: Some(registers::CommandPosition::write(target).expect("tested infallible")),
+            stop_position: Some(
+                registers::CommandStopPosition::write(stop_at).expect("tested infallible"),
+            ),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a command template with the [`registers::VelocityLimit`] set to `max_velocity` in rev/s
+    /// and the [`registers::AccelerationLimit`] set to `max_acceleration` in rev/s², and nothing else.
+    ///
+    /// A default [`Position`] leaves the limits of the controller as configured, which is unlimited
+    /// unless `servo.default_velocity_limit` and `servo.default_accel_limit` are set, so a large step in
+    /// the commanded position moves the motor as fast as it can. Starting each command from this template
+    /// instead makes the controller plan a limited trajectory to the target. The limits are sent with every
+    /// command, so choose them for the motor and its load.
+    ///
+    /// ```rust
+    /// # use moteus::frame::Position;
+    /// # use moteus::registers::{CommandPosition, Writeable};
+    /// let command = Position {
+    ///     position: Some(CommandPosition::write(2.0)?),
+    ///     ..Position::safe(1.0, 2.0)
+    /// };
+    /// # Ok::<(), moteus::RegisterError>(())
+    /// ```
+    pub fn safe(max_velocity: f32, max_acceleration: f32) -> Self {
+        Self {
+            velocity_limit: Some(
+                registers::VelocityLimit::write(max_velocity).expect("tested infallible"),
+            ),
+            acceleration_limit: Some(
+                registers::AccelerationLimit::write(max_acceleration).expect("tested infallible"),
+            ),
+            ..Self::default()
+        }
+    }
 }
 
 impl From<Position> for FrameBuilder {
@@ -100,6 +168,227 @@ impl From<Position> for FrameBuilder {
     }
 }
 
+/// Sets the mode to `registers::Modes::Position` with the kp and kd scales set to zero, for pure torque control.
+///
+/// The [`registers::CommandPosition`] and [`registers::CommandVelocity`] are set to `f32::NAN`
+/// so only the feedforward torque is applied.
+/// Each field is optional, and if a field is `None`, the corresponding register is omitted from the frame.
+#[derive(Debug, Default, Clone)]
+pub struct Torque {
+    /// The `torque` field is used to set the [`registers::CommandFeedforwardTorque`] of the motor.
+    pub torque: Option<Write<registers::CommandFeedforwardTorque>>,
+    /// The `max_torque` field is used to set the [`registers::CommandPositionMaxTorque`] of the motor.
+    pub max_torque: Option<Write<registers::CommandPositionMaxTorque>>,
+}
+
+impl From<Torque> for FrameBuilder {
+    fn from(torque: Torque) -> Self {
+        Position {
+            position: Some(registers::CommandPosition::write(f32::NAN).expect("tested infallible")),
+            velocity: Some(registers::CommandVelocity::write(f32::NAN).expect("tested infallible")),
+            feedforward_torque: torque.torque,
+            kp_scale: Some(registers::CommandKpScale::write(0.0).expect("tested infallible")),
+            kd_scale: Some(registers::CommandKdScale::write(0.0).expect("tested infallible")),
+            maximum_torque: torque.max_torque,
+            ..Position::default()
+        }
+        .into()
+    }
+}
+
+/// Sets the mode to `registers::Modes::VoltageDq`, applying a voltage in the DQ frame directly.
+///
+/// This is a low level mode intended for commissioning and motor characterization.
+/// Each field is optional, and if a field is `None`, the corresponding register is omitted from the frame.
+/// Both registers use the voltage mapping, so the values are in volts at every resolution.
+#[derive(Debug, Default, Clone)]
+pub struct VoltageDq {
+    /// The `d` field is used to set the [`registers::VoltageDqD`] of the motor.
+    pub d: Option<Write<registers::VoltageDqD>>,
+    /// The `q` field is used to set the [`registers::VoltageDqQ`] of the motor.
+    pub q: Option<Write<registers::VoltageDqQ>>,
+}
+
+impl From<VoltageDq> for FrameBuilder {
+    fn from(voltage: VoltageDq) -> Self {
+        let mut builder = Frame::builder();
+        builder
+            .add(registers::Mode::write(registers::Modes::VoltageDq).expect("tested infallible"));
+        if let Some(d) = voltage.d {
+            builder.add(d);
+        }
+        if let Some(q) = voltage.q {
+            builder.add(q);
+        }
+        builder
+    }
+}
+
+/// Sets the mode to `registers::Modes::Current`, commanding the currents in the DQ frame directly.
+///
+/// The current loop runs without the position loop, so nothing limits the speed of the motor except its load.
+/// Each field is optional, and if a field is `None`, the corresponding register is omitted from the frame.
+/// Both registers use the current mapping, which matches the scaling of the firmware: steps of 1A at `Int8`,
+/// 0.1A at `Int16` and 0.001A at `Int32`, so the values are in amps at every resolution.
+#[derive(Debug, Default, Clone)]
+pub struct Current {
+    /// The `q_current` field is used to set the [`registers::CommandQcurrent`] of the motor, which produces torque.
+    pub q_current: Option<Write<registers::CommandQcurrent>>,
+    /// The `d_current` field is used to set the [`registers::CommandDcurrent`] of the motor, usually left at zero.
+    pub d_current: Option<Write<registers::CommandDcurrent>>,
+}
+
+impl From<Current> for FrameBuilder {
+    fn from(current: Current) -> Self {
+        let mut builder = Frame::builder();
+        builder.add(registers::Mode::write(registers::Modes::Current).expect("tested infallible"));
+        if let Some(q) = current.q_current {
+            builder.add(q);
+        }
+        if let Some(d) = current.d_current {
+            builder.add(d);
+        }
+        builder
+    }
+}
+
+/// Sets the mode to `registers::Modes::MeasureInd`, used to measure the inductance of the motor during calibration.
+///
+/// The firmware measures the inductance by applying a square wave voltage to the d axis, but the amplitude and
+/// period of the wave are not exposed as registers. They are set with the `d ind` diagnostic command, which
+/// also enters the mode, so use [`crate::Controller::measure_inductance`] to start a measurement.
+/// This frame only writes the [`registers::Mode`], and without the parameters set by `d ind` no voltage is applied.
+#[derive(Debug, Default, Clone)]
+pub struct MeasureInductance;
+
+impl From<MeasureInductance> for FrameBuilder {
+    fn from(_: MeasureInductance) -> FrameBuilder {
+        let mut builder = Frame::builder();
+        builder
+            .add(registers::Mode::write(registers::Modes::MeasureInd).expect("tested infallible"));
+        builder
+    }
+}
+
+/// The states of the GPIO pins of an aux port, one bit per pin.
+///
+/// Used to build the value written to [`registers::Aux1gpioCommand`] and [`registers::Aux2gpioCommand`],
+/// and to decode [`registers::Aux1gpioStatus`] and [`registers::Aux2gpioStatus`].
+/// Only pins configured as digital outputs on the moteus are driven.
+///
+/// ```rust
+/// # use moteus::frame::GpioPins;
+/// let pins = GpioPins::new().set(0, true).set(3, true);
+/// assert_eq!(pins.bits(), 0b1001);
+/// assert!(pins.pin(3));
+/// assert!(!pins.pin(1));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GpioPins(u8);
+
+impl GpioPins {
+    /// Creates a [`GpioPins`] with every pin low
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the state of `pin`. Pins greater than 7 are ignored.
+    pub fn set(self, pin: u8, state: bool) -> Self {
+        let Some(mask) = 1u8.checked_shl(pin as u32) else {
+            return self;
+        };
+        if state {
+            Self(self.0 | mask)
+        } else {
+            Self(self.0 & !mask)
+        }
+    }
+
+    /// Returns the state of `pin`. Pins greater than 7 are always low.
+    pub fn pin(&self, pin: u8) -> bool {
+        1u8.checked_shl(pin as u32)
+            .is_some_and(|mask| self.0 & mask != 0)
+    }
+
+    /// Returns the value of the register
+    pub fn bits(&self) -> i8 {
+        self.0 as i8
+    }
+}
+
+impl From<i8> for GpioPins {
+    fn from(bits: i8) -> Self {
+        Self(bits as u8)
+    }
+}
+
+/// Sets the GPIO outputs of the aux ports, without changing the mode.
+///
+/// Each field is optional, and if a field is `None`, the corresponding register is omitted from the frame.
+#[derive(Debug, Default, Clone)]
+pub struct GpioCommand {
+    /// The `aux1` field is used to set the [`registers::Aux1gpioCommand`] of the motor.
+    pub aux1: Option<GpioPins>,
+    /// The `aux2` field is used to set the [`registers::Aux2gpioCommand`] of the motor.
+    pub aux2: Option<GpioPins>,
+}
+
+impl From<GpioCommand> for FrameBuilder {
+    fn from(gpio: GpioCommand) -> Self {
+        let mut builder = Frame::builder();
+        if let Some(a) = gpio.aux1 {
+            builder.add(registers::Aux1gpioCommand::write(a.bits()).expect("tested infallible"));
+        }
+        if let Some(a) = gpio.aux2 {
+            builder.add(registers::Aux2gpioCommand::write(a.bits()).expect("tested infallible"));
+        }
+        builder
+    }
+}
+
+/// Sets the duty cycle of the PWM outputs of the aux ports, without changing the mode.
+///
+/// Index `i` of each array sets the output of pin `i` of the port, from [`registers::Aux1pwm1`] for pin 0
+/// to [`registers::Aux1pwm5`] for pin 4. The duty cycle is from 0 to 1, and only pins configured as
+/// PWM outputs on the moteus are driven. If an entry is `None`, the corresponding register is omitted from the frame.
+///
+/// ```rust
+/// # use moteus::frame::PwmOutputs;
+/// # use moteus::FrameBuilder;
+/// let mut pwm = PwmOutputs::default();
+/// pwm.aux2[1] = Some(0.25);
+/// let frame: FrameBuilder = pwm.into();
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PwmOutputs {
+    /// The duty cycle of each pin of aux1, written to [`registers::Aux1pwm1`] to [`registers::Aux1pwm5`]
+    pub aux1: [Option<f32>; 5],
+    /// The duty cycle of each pin of aux2, written to [`registers::Aux2pwm1`] to [`registers::Aux2pwm5`]
+    pub aux2: [Option<f32>; 5],
+}
+
+impl From<PwmOutputs> for FrameBuilder {
+    fn from(pwm: PwmOutputs) -> Self {
+        fn add<R: Writeable<INNER = f32>>(builder: &mut FrameBuilder, duty: Option<f32>) {
+            if let Some(duty) = duty {
+                builder.add(R::write(duty).expect("tested infallible"));
+            }
+        }
+        let mut builder = Frame::builder();
+        add::<registers::Aux1pwm1>(&mut builder, pwm.aux1[0]);
+        add::<registers::Aux1pwm2>(&mut builder, pwm.aux1[1]);
+        add::<registers::Aux1pwm3>(&mut builder, pwm.aux1[2]);
+        add::<registers::Aux1pwm4>(&mut builder, pwm.aux1[3]);
+        add::<registers::Aux1pwm5>(&mut builder, pwm.aux1[4]);
+        add::<registers::Aux2pwm1>(&mut builder, pwm.aux2[0]);
+        add::<registers::Aux2pwm2>(&mut builder, pwm.aux2[1]);
+        add::<registers::Aux2pwm3>(&mut builder, pwm.aux2[2]);
+        add::<registers::Aux2pwm4>(&mut builder, pwm.aux2[3]);
+        add::<registers::Aux2pwm5>(&mut builder, pwm.aux2[4]);
+        builder
+    }
+}
+
 /// Specify which query is merged into the frame being sent.
 #[derive(Debug, Clone)]
 pub enum QueryType {
@@ -140,7 +429,12 @@ pub struct Query {
     pub temperature: Option<Read<registers::Temperature>>,
     pub fault: Option<Read<registers::Fault>>,
     pub aux1_gpio: Option<Read<registers::Aux1gpioStatus>>,
-    pub aux2_gpio: Option<Read<registers::Aux1gpioStatus>>,
+    pub aux2_gpio: Option<Read<registers::Aux2gpioStatus>>,
+    pub position_command: Option<Read<registers::PositionCommand>>,
+    #[cfg(feature = "aux_index_raw")]
+    pub aux1_index_raw: Option<Read<registers::Aux1IndexRaw>>,
+    #[cfg(feature = "aux_index_raw")]
+    pub aux2_index_raw: Option<Read<registers::Aux2IndexRaw>>,
 
     pub extra: Option<Vec<registers::RegisterData>>,
 }
@@ -151,6 +445,65 @@ impl Query {
         Self::default()
     }
 
+    /// Enables reading the [`registers::QCurrent`] and [`registers::DCurrent`] with resolution `Resolution::Float`.
+    /// Use [`crate::ResponseFrame::currents`] to read both from the response.
+    pub fn with_currents(mut self) -> Self {
+        self.q_current = Some(registers::QCurrent::read_with_resolution(Resolution::Float));
+        self.d_current = Some(registers::DCurrent::read_with_resolution(Resolution::Float));
+        self
+    }
+
+    /// Enables reading the [`registers::PositionCommand`] with resolution `Resolution::Float`.
+    /// Use [`crate::ResponseFrame::position_command`] to read it from the response.
+    pub fn with_position_command(mut self) -> Self {
+        self.position_command = Some(registers::PositionCommand::read_with_resolution(
+            Resolution::Float,
+        ));
+        self
+    }
+
+    /// Enables reading the [`registers::Aux1IndexRaw`] and [`registers::Aux2IndexRaw`].
+    /// Use [`crate::ResponseFrame::aux_index_raw`] to read both from the response.
+    #[cfg(feature = "aux_index_raw")]
+    pub fn with_aux_index_raw(mut self) -> Self {
+        self.aux1_index_raw = Some(registers::Aux1IndexRaw::read());
+        self.aux2_index_raw = Some(registers::Aux2IndexRaw::read());
+        self
+    }
+
+    /// Returns the addresses of the registers the query will read, in the order they are added to the frame.
+    pub fn enabled_registers(&self) -> Vec<registers::RegisterAddr> {
+        fn addr<R: Readable>(read: &Option<Read<R>>) -> Option<registers::RegisterAddr> {
+            read.as_ref().map(|_| R::address())
+        }
+        [
+            addr(&self.mode),
+            addr(&self.position),
+            addr(&self.velocity),
+            addr(&self.torque),
+            addr(&self.q_current),
+            addr(&self.d_current),
+            addr(&self.abs_position),
+            addr(&self.motor_temperature),
+            addr(&self.trajectory_complete),
+            addr(&self.home_state),
+            addr(&self.voltage),
+            addr(&self.temperature),
+            addr(&self.fault),
+            addr(&self.aux1_gpio),
+            addr(&self.aux2_gpio),
+            addr(&self.position_command),
+            #[cfg(feature = "aux_index_raw")]
+            addr(&self.aux1_index_raw),
+            #[cfg(feature = "aux_index_raw")]
+            addr(&self.aux2_index_raw),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.extra.iter().flatten().map(|r| r.address))
+        .collect()
+    }
+
     /// Extends the default query with extra registers.
     pub fn new_with_extra<T>(extra: T) -> Self
     where
@@ -184,6 +537,11 @@ impl Default for Query {
             fault: Some(registers::Fault::read_with_resolution(Resolution::Int8)),
             aux1_gpio: None,
             aux2_gpio: None,
+            position_command: None,
+            #[cfg(feature = "aux_index_raw")]
+            aux1_index_raw: None,
+            #[cfg(feature = "aux_index_raw")]
+            aux2_index_raw: None,
             extra: None,
         }
     }
@@ -240,6 +598,17 @@ impl From<Query> for FrameBuilder {
         if let Some(a) = query.aux2_gpio {
             builder.add(a);
         }
+        if let Some(p) = query.position_command {
+            builder.add(p);
+        }
+        #[cfg(feature = "aux_index_raw")]
+        if let Some(a) = query.aux1_index_raw {
+            builder.add(a);
+        }
+        #[cfg(feature = "aux_index_raw")]
+        if let Some(a) = query.aux2_index_raw {
+            builder.add(a);
+        }
         if let Some(extra) = query.extra {
             for e in extra {
                 builder.add(e);
@@ -274,6 +643,19 @@ mod tests {
         let _ = c.query(1, QueryType::DefaultAnd(custom));
     }
 
+    #[test]
+    fn test_stop() {
+        // matches the payload of `make_stop` in the reference Python library
+        let frame: FrameBuilder = Stop.into();
+        assert_eq!(frame.build().as_bytes().unwrap(), vec![0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_measure_inductance() {
+        let frame: FrameBuilder = MeasureInductance.into();
+        assert_eq!(frame.build().as_bytes().unwrap(), vec![0x01, 0x00, 0x0e]);
+    }
+
     #[test]
     fn test_query_parse() {
         let recv: FdCanUSBFrame =
@@ -302,5 +684,238 @@ mod tests {
         let _: FrameBuilder = Stop.into();
         let _: FrameBuilder = Position::hold().into();
         let _: FrameBuilder = Position::default().into();
+        let _: FrameBuilder = Position::move_to_with_stop(f32::NAN, f32::INFINITY).into();
+        let _: FrameBuilder = VoltageDq::default().into();
+        let _: FrameBuilder = Torque::default().into();
+        let _: FrameBuilder = PwmOutputs {
+            aux1: [Some(f32::NAN); 5],
+            aux2: [Some(2.0); 5],
+        }
+        .into();
+        let _: FrameBuilder = GpioCommand {
+            aux1: Some(GpioPins::from(-1)),
+            aux2: Some(GpioPins::new()),
+        }
+        .into();
+    }
+
+    #[test]
+    fn test_gpio() {
+        let pins = GpioPins::new().set(1, true).set(7, true).set(8, true);
+        assert_eq!(pins.bits(), 0b1000_0010u8 as i8);
+        assert!(!pins.set(7, false).pin(7));
+        assert!(!pins.pin(8));
+
+        let builder: FrameBuilder = GpioCommand {
+            aux2: Some(pins),
+            ..Default::default()
+        }
+        .into();
+        // writes Aux2gpioCommand only, as an Int8
+        assert_eq!(
+            builder.build().as_bytes().unwrap(),
+            vec![0x01, 0x5d, 0b1000_0010]
+        );
+
+        let response = crate::ResponseFrame::from_bytes(&[0x22, 0x5e, 0x05, 0x02]).unwrap();
+        let aux1: GpioPins = response
+            .get::<registers::Aux1gpioStatus>()
+            .unwrap()
+            .value()
+            .into();
+        let aux2: GpioPins = response
+            .get::<registers::Aux2gpioStatus>()
+            .unwrap()
+            .value()
+            .into();
+        assert!(aux1.pin(0) && !aux1.pin(1) && aux1.pin(2));
+        assert!(aux2.pin(1));
+    }
+
+    #[test]
+    fn test_move_to_with_stop() {
+        let builder: FrameBuilder = Position::move_to_with_stop(1.0, 0.5).into();
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandPosition>()
+                .unwrap()
+                .value(),
+            1.0
+        );
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandStopPosition>()
+                .unwrap()
+                .value(),
+            0.5
+        );
+        assert!(builder.get_write::<registers::CommandVelocity>().is_none());
+    }
+
+    #[test]
+    fn test_safe() {
+        let builder: FrameBuilder = Position::safe(1.5, 3.0).into();
+        assert_eq!(
+            builder
+                .get_write::<registers::VelocityLimit>()
+                .unwrap()
+                .value(),
+            1.5
+        );
+        assert_eq!(
+            builder
+                .get_write::<registers::AccelerationLimit>()
+                .unwrap()
+                .value(),
+            3.0
+        );
+        assert!(builder.get_write::<registers::CommandPosition>().is_none());
+    }
+
+    #[test]
+    fn test_pwm_outputs() {
+        let mut pwm = PwmOutputs::default();
+        pwm.aux1[0] = Some(0.5);
+        pwm.aux2[4] = Some(1.0);
+        let builder: FrameBuilder = pwm.into();
+        let mut expected = vec![0x0d, 0x76];
+        expected.extend(0.5f32.to_le_bytes());
+        expected.extend([0x0d, 0x7f]);
+        expected.extend(1.0f32.to_le_bytes());
+        assert_eq!(builder.build().as_bytes().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_torque() {
+        let builder: FrameBuilder = Torque {
+            torque: Some(registers::CommandFeedforwardTorque::write(0.5).unwrap()),
+            max_torque: None,
+        }
+        .into();
+        assert_eq!(
+            builder.get_write::<registers::Mode>().unwrap().value(),
+            registers::Modes::Position
+        );
+        assert!(builder
+            .get_write::<registers::CommandPosition>()
+            .unwrap()
+            .value()
+            .is_nan());
+        assert!(builder
+            .get_write::<registers::CommandVelocity>()
+            .unwrap()
+            .value()
+            .is_nan());
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandKpScale>()
+                .unwrap()
+                .value(),
+            0.0
+        );
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandKdScale>()
+                .unwrap()
+                .value(),
+            0.0
+        );
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandFeedforwardTorque>()
+                .unwrap()
+                .value(),
+            0.5
+        );
+        assert!(builder
+            .get_write::<registers::CommandPositionMaxTorque>()
+            .is_none());
+    }
+
+    #[test]
+    fn test_enabled_registers() {
+        use registers::RegisterAddr;
+        assert_eq!(
+            Query::default().enabled_registers(),
+            vec![
+                RegisterAddr::Mode,
+                RegisterAddr::Position,
+                RegisterAddr::Velocity,
+                RegisterAddr::Torque,
+                RegisterAddr::Voltage,
+                RegisterAddr::Temperature,
+                RegisterAddr::Fault,
+            ]
+        );
+        let query = Query {
+            velocity: None,
+            ..Query::new_with_extra([registers::ControlPosition::read().into()])
+        }
+        .with_currents();
+        let enabled = query.enabled_registers();
+        assert!(!enabled.contains(&RegisterAddr::Velocity));
+        assert!(enabled.contains(&RegisterAddr::QCurrent));
+        assert_eq!(enabled.last(), Some(&RegisterAddr::ControlPosition));
+    }
+
+    #[test]
+    fn test_position_command() {
+        let query = Query::default().with_position_command();
+        assert_eq!(
+            query.enabled_registers().last(),
+            Some(&registers::RegisterAddr::PositionCommand)
+        );
+
+        let mut reply = vec![0x2d, 0x34];
+        reply.extend(1.25f32.to_le_bytes());
+        let response = crate::ResponseFrame::from_bytes(&reply).unwrap();
+        assert_eq!(response.position_command(), Some(1.25));
+    }
+
+    #[cfg(feature = "aux_index_raw")]
+    #[test]
+    fn test_aux_index_raw() {
+        use registers::RegisterAddr;
+        let query = Query::default().with_aux_index_raw();
+        let enabled = query.enabled_registers();
+        assert!(enabled.ends_with(&[RegisterAddr::Aux1IndexRaw, RegisterAddr::Aux2IndexRaw]));
+
+        let response = crate::ResponseFrame::from_bytes(&[0x22, 0x59, 3, 0xfe]).unwrap();
+        assert_eq!(response.aux_index_raw(), Some((3, -2)));
+    }
+
+    #[test]
+    fn test_voltage_dq() {
+        let builder: FrameBuilder = VoltageDq {
+            d: Some(registers::VoltageDqD::write_with_resolution(1.5, Resolution::Int16).unwrap()),
+            q: Some(registers::VoltageDqQ::write_with_resolution(-2.0, Resolution::Int16).unwrap()),
+        }
+        .into();
+        let bytes = builder.build().as_bytes().unwrap();
+        // Mode, then d and q in 0.1V steps
+        assert_eq!(bytes, vec![0x01, 0x00, 0x08, 0x06, 0x1a, 15, 0, 236, 255]);
+    }
+
+    #[test]
+    fn test_current() {
+        let builder: FrameBuilder = Current {
+            q_current: Some(
+                registers::CommandQcurrent::write_with_resolution(2.5, Resolution::Int16).unwrap(),
+            ),
+            d_current: Some(
+                registers::CommandDcurrent::write_with_resolution(-1.0, Resolution::Int16).unwrap(),
+            ),
+        }
+        .into();
+        let bytes = builder.build().as_bytes().unwrap();
+        // Mode, then q and d in 0.1A steps
+        assert_eq!(bytes, vec![0x01, 0x00, 0x09, 0x06, 0x1c, 25, 0, 246, 255]);
+
+        // 0.001A steps at Int32
+        let q = registers::CommandQcurrent::write_with_resolution(10.0, Resolution::Int32).unwrap();
+        assert_eq!(
+            registers::RegisterData::from(q).data,
+            Some(10_000i32.to_le_bytes().to_vec())
+        );
     }
 }
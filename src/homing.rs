@@ -0,0 +1,141 @@
+//! Tracking of the [`HomeStates`] transitions while homing.
+
+use crate::registers::HomeStates;
+use std::time::{Duration, Instant};
+
+/// The status reported by a [`HomingMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HomingStatus {
+    /// No [`HomeStates`] has been observed yet
+    Unknown,
+    /// Homing is in progress and the controller is in the given state
+    InProgress(HomeStates),
+    /// The target state has been reached
+    Complete,
+    /// The state has not changed within the timeout
+    Stalled(HomeStates),
+}
+
+/// Follows the [`HomeStates`] transitions (Relative → Rotor → Output) reported by a controller while homing.
+///
+/// Feed it each [`registers::HomeState`](crate::registers::HomeState) reading with [`HomingMonitor::update`].
+///
+/// ```rust
+/// # use moteus::{HomingMonitor, HomingStatus};
+/// # use moteus::registers::HomeStates;
+/// # use std::time::Duration;
+/// let mut monitor = HomingMonitor::new(HomeStates::Output, Duration::from_secs(5));
+/// assert_eq!(monitor.update(HomeStates::Relative), HomingStatus::InProgress(HomeStates::Relative));
+/// assert_eq!(monitor.update(HomeStates::Rotor), HomingStatus::InProgress(HomeStates::Rotor));
+/// assert_eq!(monitor.update(HomeStates::Output), HomingStatus::Complete);
+/// assert_eq!(monitor.progress(), 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HomingMonitor {
+    target: HomeStates,
+    timeout: Duration,
+    state: Option<HomeStates>,
+    last_change: Instant,
+}
+
+impl HomingMonitor {
+    /// Creates a new [`HomingMonitor`] that completes when `target` is reached,
+    /// and reports [`HomingStatus::Stalled`] if the state does not change within `timeout`.
+    pub fn new(target: HomeStates, timeout: Duration) -> Self {
+        Self {
+            target,
+            timeout,
+            state: None,
+            last_change: Instant::now(),
+        }
+    }
+
+    /// Records a [`HomeStates`] reading and returns the resulting [`HomingStatus`]
+    pub fn update(&mut self, state: HomeStates) -> HomingStatus {
+        self.update_at(state, Instant::now())
+    }
+
+    fn update_at(&mut self, state: HomeStates, now: Instant) -> HomingStatus {
+        if self.state != Some(state) {
+            self.state = Some(state);
+            self.last_change = now;
+        }
+        self.status_at(now)
+    }
+
+    /// Returns the current [`HomingStatus`] without recording a new reading
+    pub fn status(&self) -> HomingStatus {
+        self.status_at(Instant::now())
+    }
+
+    fn status_at(&self, now: Instant) -> HomingStatus {
+        match self.state {
+            None => HomingStatus::Unknown,
+            Some(state) if state as u8 >= self.target as u8 => HomingStatus::Complete,
+            Some(state) if now.duration_since(self.last_change) > self.timeout => {
+                HomingStatus::Stalled(state)
+            }
+            Some(state) => HomingStatus::InProgress(state),
+        }
+    }
+
+    /// The last observed [`HomeStates`], if any
+    pub fn phase(&self) -> Option<HomeStates> {
+        self.state
+    }
+
+    /// The fraction of the transitions to the target state that have been completed, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        match self.state {
+            None => 0.0,
+            Some(_) if self.target == HomeStates::Relative => 1.0,
+            Some(state) => (state as u8 as f32 / self.target as u8 as f32).min(1.0),
+        }
+    }
+
+    /// Forgets the observed state, e.g. before homing again
+    pub fn reset(&mut self) {
+        self.state = None;
+        self.last_change = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stalled() {
+        let start = Instant::now();
+        let mut monitor = HomingMonitor::new(HomeStates::Output, Duration::from_secs(1));
+        assert_eq!(monitor.status_at(start), HomingStatus::Unknown);
+        assert_eq!(
+            monitor.update_at(HomeStates::Relative, start),
+            HomingStatus::InProgress(HomeStates::Relative)
+        );
+        assert_eq!(
+            monitor.update_at(HomeStates::Relative, start + Duration::from_millis(1500)),
+            HomingStatus::Stalled(HomeStates::Relative)
+        );
+        assert_eq!(
+            monitor.update_at(HomeStates::Rotor, start + Duration::from_millis(1600)),
+            HomingStatus::InProgress(HomeStates::Rotor)
+        );
+        assert_eq!(monitor.progress(), 0.5);
+        assert_eq!(
+            monitor.update_at(HomeStates::Output, start + Duration::from_secs(10)),
+            HomingStatus::Complete
+        );
+    }
+
+    #[test]
+    fn test_rotor_target() {
+        let mut monitor = HomingMonitor::new(HomeStates::Rotor, Duration::from_secs(1));
+        assert_eq!(monitor.update(HomeStates::Rotor), HomingStatus::Complete);
+        assert_eq!(monitor.update(HomeStates::Output), HomingStatus::Complete);
+        assert_eq!(monitor.progress(), 1.0);
+        monitor.reset();
+        assert_eq!(monitor.phase(), None);
+        assert_eq!(monitor.progress(), 0.0);
+    }
+}
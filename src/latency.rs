@@ -0,0 +1,132 @@
+//! Host side statistics of the command → response round trip time.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Accumulates round trip latencies over a sliding window.
+///
+/// Enable it on a [`crate::Controller`] with [`crate::Controller::enable_latency_stats`],
+/// each transfer that expects a response will then record a sample.
+///
+/// ```rust
+/// # use moteus::LatencyStats;
+/// # use std::time::Duration;
+/// let mut stats = LatencyStats::new(100);
+/// stats.record(Duration::from_micros(400));
+/// stats.record(Duration::from_micros(600));
+/// assert_eq!(stats.mean(), Some(Duration::from_micros(500)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyStats {
+    window: usize,
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyStats {
+    /// Creates a new [`LatencyStats`] keeping the last `window` samples.
+    /// A `window` of 0 is treated as 1.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Records a sample, dropping the oldest sample if the window is full.
+    pub fn record(&mut self, latency: Duration) {
+        if self.samples.len() == self.window {
+            let _ = self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    /// Removes all samples
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// The number of samples currently in the window
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if no samples have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The smallest latency in the window
+    pub fn min(&self) -> Option<Duration> {
+        self.samples.iter().min().copied()
+    }
+
+    /// The largest latency in the window
+    pub fn max(&self) -> Option<Duration> {
+        self.samples.iter().max().copied()
+    }
+
+    /// The mean latency of the window
+    pub fn mean(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: Duration = self.samples.iter().sum();
+        Some(total / self.samples.len() as u32)
+    }
+
+    /// The latency below which `percentile` percent of the samples fall (nearest rank).
+    /// `percentile` is clamped to `0.0..=100.0`.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// The 99th percentile latency of the window
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        let stats = LatencyStats::new(10);
+        assert!(stats.is_empty());
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.p99(), None);
+    }
+
+    #[test]
+    fn test_window() {
+        let mut stats = LatencyStats::new(3);
+        for ms in 1..=5 {
+            stats.record(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats.min(), Some(Duration::from_millis(3)));
+        assert_eq!(stats.max(), Some(Duration::from_millis(5)));
+        assert_eq!(stats.mean(), Some(Duration::from_millis(4)));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut stats = LatencyStats::new(100);
+        for ms in 1..=100 {
+            stats.record(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.p99(), Some(Duration::from_millis(99)));
+        assert_eq!(stats.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(stats.percentile(100.0), Some(Duration::from_millis(100)));
+        assert_eq!(stats.percentile(0.0), Some(Duration::from_millis(1)));
+    }
+}
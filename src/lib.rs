@@ -28,13 +28,33 @@ unused_qualifications,
 )]
 #![warn(clippy::unwrap_used)]
 
+mod abs_position;
+mod block;
 mod bus;
+mod capture;
+#[cfg(feature = "serde")]
+pub mod description;
+mod driver;
+mod encoder;
 mod error;
 pub mod frame;
+mod homing;
+mod latency;
 mod protocol;
+mod reset;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod throttle;
 mod transport;
+mod voltage;
+mod watchdog;
 
-pub use bus::Controller;
+pub use abs_position::AbsPositionRef;
+pub use block::RegisterBlock;
+pub use bus::{ClockTrimStatus, Controller, IdResponse, TelemetryStream};
+pub use capture::{FrameLog, FrameLogReader, LoggedFrame};
+pub use driver::{DriverCondition, DriverFaultReport};
+pub use encoder::{EncoderDiagnostics, EncoderReadings, EncoderStatus, ENCODER_COUNT};
 pub use error::*;
 #[cfg(feature = "fdcanusb")]
 pub use fdcanusb;
@@ -42,4 +62,12 @@ pub use fdcanusb;
 pub use fdcanusb::serial2;
 #[cfg(feature = "fdcanusb")]
 pub use fdcanusb::FdCanUSB;
-pub use protocol::{registers, Frame, FrameBuilder, Resolution, ResponseFrame};
+pub use homing::{HomingMonitor, HomingStatus};
+pub use latency::LatencyStats;
+pub use protocol::{
+    registers, BusTiming, Frame, FrameBuilder, Resolution, ResolutionPolicy, ResponseFrame,
+};
+pub use reset::ResetDetector;
+pub use throttle::{Throttle, ThrottlePolicy};
+pub use voltage::{VoltageStatus, VoltageThresholds};
+pub use watchdog::{WatchdogGuard, WatchdogStatus, WATCHDOG_TIMEOUT};
@@ -0,0 +1,158 @@
+//! Encoding and decoding of the diagnostic stream subframes.
+//!
+//! The diagnostic stream carries the ascii protocol used by `moteus_tool` and `tview`
+//! (e.g. `conf get motor.poles`) inside CAN-FD frames.
+
+use crate::protocol::registers::FrameRegisters;
+use crate::FrameParseError;
+
+/// The channel used by the moteus diagnostic stream.
+pub(crate) const DIAGNOSTIC_CHANNEL: u8 = 1;
+/// The maximum number of bytes sent or requested in a single diagnostic frame.
+pub(crate) const MAX_DIAGNOSTIC_CHUNK: usize = 48;
+/// The number of consecutive empty polls after which a diagnostic command is considered unanswered.
+pub(crate) const MAX_EMPTY_POLLS: usize = 100;
+
+/// Returns the bytes of a frame writing `data` to the diagnostic stream.
+/// `data` must be no more than [`MAX_DIAGNOSTIC_CHUNK`] bytes.
+pub(crate) fn write_frame(channel: u8, data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(3 + data.len());
+    buf.push(FrameRegisters::StreamClientData as u8);
+    buf.push(channel);
+    buf.push(data.len() as u8);
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// Returns the bytes of a frame polling the diagnostic stream for up to `max_len` bytes.
+pub(crate) fn poll_frame(channel: u8, max_len: u8) -> Vec<u8> {
+    vec![FrameRegisters::StreamClientPoll as u8, channel, max_len]
+}
+
+/// Parses the data from a diagnostic stream reply.
+pub(crate) fn parse_reply(buf: &[u8], channel: u8) -> Result<Vec<u8>, FrameParseError> {
+    let Some(&register) = buf.first() else {
+        return Ok(Vec::new());
+    };
+    if register == FrameRegisters::Nop as u8 {
+        return Ok(Vec::new());
+    }
+    if register != FrameRegisters::StreamServerData as u8 {
+        return Err(FrameParseError::InvalidFrameRegister(register));
+    }
+    match (buf.get(1), buf.get(2)) {
+        (Some(&c), Some(&len)) if c == channel && buf.len() >= 3 + len as usize => {
+            Ok(buf[3..3 + len as usize].to_vec())
+        }
+        _ => Err(FrameParseError::InvalidDiagnosticReply),
+    }
+}
+
+/// Removes and returns the first non-empty line from `buf`, without the line ending.
+/// Returns [`None`] if `buf` does not contain a complete non-empty line.
+pub(crate) fn take_line(buf: &mut Vec<u8>) -> Option<String> {
+    while let Some(end) = buf.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = buf.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        if !line.is_empty() {
+            return Some(line);
+        }
+    }
+    None
+}
+
+/// Parses the reply to a `conf get` command into an `f32`.
+pub(crate) fn parse_f32(reply: &str) -> Option<f32> {
+    reply.trim().parse().ok()
+}
+
+/// Returns the `d` command for `command`, which may or may not already start with `d `.
+pub(crate) fn d_command(command: &str) -> String {
+    let command = command.trim();
+    let command = command.strip_prefix("d ").unwrap_or(command).trim_start();
+    format!("d {command}")
+}
+
+/// Returns `true` if a reply is the `OK` sentinel, optionally followed by more text.
+pub(crate) fn is_ok(reply: &str) -> bool {
+    reply == "OK" || reply.starts_with("OK ")
+}
+
+/// Parses the reply to `tel get firmware` into the version string.
+///
+/// A leading label such as `firmware.version` or `version:` is removed.
+pub(crate) fn parse_version(reply: &str) -> Option<String> {
+    let reply = reply.trim();
+    let version = match reply.split_once(char::is_whitespace) {
+        Some((label, rest)) if label.contains("firmware") || label.contains("version") => rest,
+        _ => reply,
+    };
+    let version = version.trim_start_matches(':').trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_write_frame() {
+        assert_eq!(
+            write_frame(DIAGNOSTIC_CHANNEL, b"tel stop\n"),
+            [&[0x40, 0x01, 0x09][..], b"tel stop\n"].concat()
+        );
+    }
+
+    #[test]
+    fn test_poll_frame() {
+        assert_eq!(poll_frame(DIAGNOSTIC_CHANNEL, 48), vec![0x42, 0x01, 48]);
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let buf = [&[0x41, 0x01, 0x04][..], b"14\r\n", &[0x50, 0x50]].concat();
+        assert_eq!(parse_reply(&buf, DIAGNOSTIC_CHANNEL).unwrap(), b"14\r\n");
+        assert!(parse_reply(&[], DIAGNOSTIC_CHANNEL).unwrap().is_empty());
+        assert!(parse_reply(&[0x41, 0x01, 0x04, b'1'], DIAGNOSTIC_CHANNEL).is_err());
+        assert!(parse_reply(&[0x41, 0x02, 0x00], DIAGNOSTIC_CHANNEL).is_err());
+    }
+
+    #[test]
+    fn test_take_line() {
+        let mut buf = b"\r\n14".to_vec();
+        assert_eq!(take_line(&mut buf), None);
+        buf.extend(b".5\r\nOK");
+        assert_eq!(take_line(&mut buf), Some("14.5".to_string()));
+        assert_eq!(buf, b"OK");
+    }
+
+    #[test]
+    fn test_d_command() {
+        assert_eq!(d_command("stop"), "d stop");
+        assert_eq!(d_command("d pos nan 0 1"), "d pos nan 0 1");
+        assert_eq!(d_command("  d  stay "), "d stay");
+        assert!(is_ok("OK"));
+        assert!(!is_ok("OKAY"));
+        assert!(!is_ok("ERR unknown command"));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0x010a"), Some("0x010a".to_string()));
+        assert_eq!(
+            parse_version("firmware.version 2024-05-02 abc123"),
+            Some("2024-05-02 abc123".to_string())
+        );
+        assert_eq!(parse_version("version: 1.2\r"), Some("1.2".to_string()));
+        assert_eq!(parse_version(" \r"), None);
+    }
+
+    #[test]
+    fn test_parse_f32() {
+        assert_eq!(parse_f32("14"), Some(14.0));
+        assert_eq!(parse_f32(" 0.250000 "), Some(0.25));
+        assert!(parse_f32("nan").unwrap().is_nan());
+        assert_eq!(parse_f32("ERR unknown"), None);
+    }
+}
@@ -1,26 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
 
 use crate::error::FrameError;
-use crate::protocol::registers::{FrameRegisters, RegisterData};
-use crate::registers::{Register, RegisterAddr, Res};
+use crate::protocol::registers::{varuint, FrameRegisters, RegisterData};
+use crate::registers::{
+    CustomRegisterData, CustomRegisters, Register, RegisterAddr, RegisterValue, Res,
+};
 use crate::{FrameParseError, Resolution};
 use fdcanusb::CanFdFrame;
 use itertools::Itertools;
 use num_traits::FromPrimitive;
 
+/// A subframe is a run of sequential registers sharing a [`FrameRegisters`] type and resolution.
 #[derive(Debug, PartialEq)]
 pub struct SubFrame {
     register: FrameRegisters,
     len: u8,
     data: Vec<RegisterData>,
+    custom: Vec<CustomRegisterData>,
 }
 
 impl SubFrame {
+    /// Creates an empty subframe
     pub fn new(register: FrameRegisters, len: u8) -> Self {
         SubFrame {
             register,
             len,
             data: Vec::new(),
+            custom: Vec::new(),
         }
     }
 
@@ -38,16 +45,37 @@ impl SubFrame {
         Ok(())
     }
 
+    /// The [`FrameRegisters`] type of the subframe
+    #[cfg(feature = "testing")]
+    pub fn register(&self) -> FrameRegisters {
+        self.register
+    }
+
+    /// The registers of the subframe
+    #[cfg(feature = "testing")]
+    pub fn registers(&self) -> &[RegisterData] {
+        &self.data
+    }
+
+    #[cfg(any(test, feature = "testing"))]
     pub(crate) fn as_bytes(&self) -> Result<Vec<u8>, FrameError> {
-        let mut buf = Vec::with_capacity(64); //TODO: SWAP WITH SUB FRAME BUF TO AVOID ALLOCATING
-        if self.len < 4 {
+        let mut buf = Vec::with_capacity(64);
+        self.as_bytes_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Appends the bytes of the subframe to `buf`
+    pub(crate) fn as_bytes_into(&self, buf: &mut Vec<u8>) -> Result<(), FrameError> {
+        let first_reg = self.data.first().ok_or(FrameError::EmptySubFrame)?;
+        // a length of 0 in the low bits means the length follows in the next byte,
+        // so only lengths from 1 to 3 fit in the low bits
+        if (1..4).contains(&self.len) {
             buf.push((self.register as u8) | self.len);
         } else {
             buf.push(self.register as u8);
             buf.push(self.len);
         }
         // write registers, takes into account unneeded sequential registers
-        let first_reg = self.data.first().ok_or(FrameError::EmptySubFrame)?;
         buf.extend(first_reg.address.address_as_bytes());
         if first_reg.data.is_some() {
             self.data.iter().for_each(|reg| {
@@ -55,96 +83,253 @@ impl SubFrame {
             });
         }
 
-        Ok(buf)
+        Ok(())
     }
 
     /// Return the parsed subframe and the number of bytes consumed
+    #[cfg(test)]
     pub(crate) fn from_bytes(buf: &[u8]) -> Result<(Option<Self>, usize), FrameParseError> {
-        if buf.is_empty() {
+        SubFrame::from_bytes_with(buf, &CustomRegisters::default())
+    }
+
+    /// Return the parsed subframe and the number of bytes consumed.
+    /// Addresses found in `custom` are parsed into [`CustomRegisterData`].
+    pub(crate) fn from_bytes_with(
+        buf: &[u8],
+        custom: &CustomRegisters,
+    ) -> Result<(Option<Self>, usize), FrameParseError> {
+        let Some(&first) = buf.first() else {
             return Ok((None, 0));
-        }
-        let frame_register = buf[0] & (0xFF - 0x03);
+        };
+        let frame_register = first & (0xFF - 0x03);
         let frame_register = FrameRegisters::from_u8(frame_register)
             .ok_or(FrameParseError::InvalidFrameRegister(frame_register))?;
         if frame_register == FrameRegisters::Nop {
             return Ok((None, 1));
         }
-        let (len_offset, len) = {
-            //get len either from bits or the next byte (increments index)
-            match buf[0] & 0x03 {
-                0 => {
-                    (1, buf[1]) //index = 1
-                }
-                l => (0, l),
+        // the length is either in the low bits, or a varuint following the register when the low bits are 0.
+        // Writers may use the varuint form for any length, not only those over 3.
+        let (len_offset, len) = match first & 0x03 {
+            0 => {
+                let (len, size) = read_varuint(&buf[1..])?;
+                let len = u8::try_from(len).map_err(|_| FrameParseError::LengthOutOfRange(len))?;
+                (size, len)
             }
+            l => (0, l),
         };
-        let initial_reg = buf[1 + len_offset];
+        let (initial_reg, reg_size) = read_varuint(&buf[1 + len_offset..])?;
+        let initial_reg = u16::try_from(initial_reg)
+            .map_err(|_| FrameParseError::AddressOutOfRange(initial_reg))?;
         // todo! added support for read/write error frame registers
         let resolution = frame_register
             .resolution()
             .ok_or(FrameParseError::UnsupportedSubframeRegister(frame_register))?;
         let index_step = resolution.size();
-        let start = 2 + len_offset;
+        let start = 1 + len_offset + reg_size;
         let end = {
             match frame_register {
                 FrameRegisters::ReadInt8
                 | FrameRegisters::ReadInt16
                 | FrameRegisters::ReadInt32
                 | FrameRegisters::ReadF32 => start,
-                _ => (len as usize * index_step) + 2 + len_offset,
+                _ => (len as usize * index_step) + start,
             }
         };
-        let data = {
-            let mut data = Vec::new();
-            for (reg_index, i) in (start..end).step_by(index_step).enumerate() {
-                let reg_addr = initial_reg as u16 + reg_index as u16;
+        let mut data = Vec::new();
+        let mut custom_data = Vec::new();
+        for (reg_index, i) in (start..end).step_by(index_step).enumerate() {
+            let reg_addr = initial_reg.checked_add(reg_index as u16).ok_or(
+                FrameParseError::AddressOutOfRange(initial_reg as u32 + reg_index as u32),
+            )?;
+            let bytes = buf
+                .get(i..i + index_step)
+                .ok_or(FrameParseError::Truncated)?;
 
-                let reg = RegisterData::from_bytes(reg_addr, &buf[i..i + index_step], resolution)?;
-                data.push(reg);
+            if RegisterAddr::from_u16(reg_addr).is_none() && custom.contains(reg_addr) {
+                custom_data.push(CustomRegisterData {
+                    address: reg_addr,
+                    resolution,
+                    data: bytes.into(),
+                });
+                continue;
             }
-            data
-        };
+            let reg = RegisterData::from_bytes(reg_addr, bytes, resolution)?;
+            data.push(reg);
+        }
         Ok((
             Some(Self {
                 register: frame_register,
                 len,
                 data,
+                custom: custom_data,
             }),
             end,
         ))
     }
 }
 
+/// Reads a varuint from the start of `buf`, returning the value and the number of bytes consumed
+fn read_varuint(buf: &[u8]) -> Result<(u32, usize), FrameParseError> {
+    let mut value = 0u32;
+    for (i, byte) in buf.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(FrameParseError::Truncated)
+}
+
 /// A response frame is a collection of registers returned from the Moteus Controller.
 /// The registers can be accessed by their type using the `get` method.
 /// Many registers can be accessed at once using the `get_many` method.
 #[derive(Debug, PartialEq)]
-pub struct ResponseFrame(Vec<RegisterData>);
+pub struct ResponseFrame {
+    registers: Vec<RegisterData>,
+    custom: Vec<CustomRegisterData>,
+    brs: Option<bool>,
+}
 
 impl ResponseFrame {
+    #[cfg(test)]
     pub(crate) fn from_bytes(buf: &[u8]) -> Result<ResponseFrame, FrameParseError> {
-        let mut results = Vec::new();
-        let mut buf = buf;
-        loop {
-            let (subframe, offset) = SubFrame::from_bytes(buf)?;
+        ResponseFrame::from_bytes_with(buf, &CustomRegisters::default())
+    }
+
+    /// Parse a response frame, accepting the addresses in `custom` as well as those in [`RegisterAddr`].
+    ///
+    /// The data of custom registers can be accessed with [`ResponseFrame::get_custom`].
+    pub fn from_bytes_with(
+        buf: &[u8],
+        custom: &CustomRegisters,
+    ) -> Result<ResponseFrame, FrameParseError> {
+        let mut registers = Vec::new();
+        let mut custom_data = Vec::new();
+        let mut offset = 0;
+        for index in 0.. {
+            let (subframe, len) =
+                SubFrame::from_bytes_with(&buf[offset..], custom).map_err(|e| {
+                    FrameParseError::Subframe {
+                        index,
+                        offset,
+                        source: Box::new(e),
+                    }
+                })?;
             if let Some(subframe) = subframe {
-                subframe.data.into_iter().for_each(|reg| {
-                    results.push(reg);
-                });
+                registers.extend(subframe.data);
+                custom_data.extend(subframe.custom);
             }
-            buf = &buf[offset..];
-            if buf.is_empty() {
+            offset += len;
+            if offset >= buf.len() {
                 break;
             }
         }
-        Ok(ResponseFrame(results))
+        Ok(ResponseFrame {
+            registers,
+            custom: custom_data,
+            brs: None,
+        })
+    }
+
+    /// Parse a response frame, rejecting anything that does not exactly match the structure sent by a moteus.
+    ///
+    /// [`ResponseFrame::from_bytes_with`] tolerates some slop, this also returns an error if:
+    /// - a subframe is not a reply subframe, such as a read or write echoed back
+    /// - a subframe holds no registers
+    /// - a register is reported more than once
+    /// - [`FrameRegisters::Nop`] padding is found between subframes, or is followed by other bytes
+    /// - the frame is not padded to exactly the CAN-FD length that carries its subframes
+    ///
+    /// Use this to validate that a device is protocol compliant, not to parse replies in normal use.
+    /// Custom registers are not accepted.
+    ///
+    /// ```rust
+    /// # use moteus::ResponseFrame;
+    /// // Mode: Stopped
+    /// assert!(ResponseFrame::from_bytes_strict(&[0x21, 0x00, 0x00]).is_ok());
+    /// // padded to 4 bytes, when 3 bytes is already a CAN-FD length
+    /// assert!(ResponseFrame::from_bytes_strict(&[0x21, 0x00, 0x00, 0x50]).is_err());
+    /// ```
+    pub fn from_bytes_strict(buf: &[u8]) -> Result<ResponseFrame, FrameParseError> {
+        let custom = CustomRegisters::default();
+        let mut registers: Vec<RegisterData> = Vec::new();
+        let mut offset = 0;
+        let mut index = 0;
+        while offset < buf.len() && buf[offset] != FrameRegisters::Nop as u8 {
+            let at = |source| FrameParseError::Subframe {
+                index,
+                offset,
+                source: Box::new(source),
+            };
+            let (subframe, len) = SubFrame::from_bytes_with(&buf[offset..], &custom).map_err(at)?;
+            if let Some(subframe) = subframe {
+                if !matches!(
+                    subframe.register,
+                    FrameRegisters::ReplyInt8
+                        | FrameRegisters::ReplyInt16
+                        | FrameRegisters::ReplyInt32
+                        | FrameRegisters::ReplyF32
+                ) {
+                    return Err(at(FrameParseError::UnsupportedSubframeRegister(
+                        subframe.register,
+                    )));
+                }
+                if subframe.data.is_empty() {
+                    return Err(at(FrameParseError::NonConforming("empty subframe")));
+                }
+                for reg in subframe.data {
+                    if registers.iter().any(|r| r.address == reg.address) {
+                        return Err(at(FrameParseError::NonConforming(
+                            "register reported more than once",
+                        )));
+                    }
+                    registers.push(reg);
+                }
+            }
+            offset += len;
+            index += 1;
+        }
+        if buf[offset..]
+            .iter()
+            .any(|byte| *byte != FrameRegisters::Nop as u8)
+        {
+            return Err(FrameParseError::NonConforming(
+                "data after the padding of the frame",
+            ));
+        }
+        if padded_len(offset) != Some(buf.len()) {
+            return Err(FrameParseError::NonConforming(
+                "frame is not padded to the CAN-FD length of its data",
+            ));
+        }
+        Ok(ResponseFrame {
+            registers,
+            custom: Vec::new(),
+            brs: None,
+        })
+    }
+
+    /// Parse the data of a received [`CanFdFrame`], keeping whether it used BRS.
+    pub(crate) fn from_can_frame(
+        frame: &CanFdFrame,
+        custom: &CustomRegisters,
+    ) -> Result<ResponseFrame, FrameParseError> {
+        let mut response = ResponseFrame::from_bytes_with(&frame.data, custom)?;
+        response.brs = frame.brs;
+        Ok(response)
+    }
+
+    /// Returns whether the reply used BRS (Bit Rate Switch), if reported by the transport.
+    /// Frames parsed from bytes with [`ResponseFrame::from_bytes_with`] return [`None`].
+    pub fn used_brs(&self) -> Option<bool> {
+        self.brs
     }
 
     /// Get a register from the response frame
     /// If the register `R` is not found in the response frame [`None`] is returned.
     pub fn get<R: Register>(&self) -> Option<Res<R>> {
         let register = R::address();
-        self.0
+        self.registers
             .iter()
             .find(|reg| reg.address == register)
             .and_then(|reg| reg.as_res::<R>().ok())
@@ -155,14 +340,232 @@ impl ResponseFrame {
     pub fn get_many<F: FnOnce(&ResponseFrame) -> Option<R>, R>(&self, f: F) -> Option<R> {
         f(self)
     }
+
+    /// Decodes every register in the response frame into its natural type, keyed by address.
+    ///
+    /// Registers that fail to decode and custom registers are not included.
+    pub fn to_map(&self) -> HashMap<RegisterAddr, RegisterValue> {
+        self.registers
+            .iter()
+            .filter_map(|reg| Some((reg.address, reg.address.read_value(reg).ok()?)))
+            .collect()
+    }
+
+    /// Compares the registers of two response frames, returning each register whose data differs as
+    /// `(address, old, new)`, sorted by address. `old` is the value in `self` and `new` the value in `other`,
+    /// [`None`] if the register is missing from that frame or fails to decode.
+    ///
+    /// Registers are compared by their raw data, so a register holding NaN in both frames is unchanged.
+    /// Custom registers are not compared.
+    pub fn diff(
+        &self,
+        other: &ResponseFrame,
+    ) -> Vec<(RegisterAddr, Option<RegisterValue>, Option<RegisterValue>)> {
+        let old: BTreeMap<RegisterAddr, &RegisterData> = self
+            .registers
+            .iter()
+            .map(|reg| (reg.address, reg))
+            .collect();
+        let new: BTreeMap<RegisterAddr, &RegisterData> = other
+            .registers
+            .iter()
+            .map(|reg| (reg.address, reg))
+            .collect();
+        let value =
+            |reg: Option<&&RegisterData>| reg.and_then(|reg| reg.address.read_value(reg).ok());
+        old.keys()
+            .chain(new.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|addr| old.get(addr) != new.get(addr))
+            .map(|addr| (*addr, value(old.get(addr)), value(new.get(addr))))
+            .collect()
+    }
+
+    /// Converts the registers of the response into writes of the same data at the same resolution,
+    /// to command a controller back to a captured state.
+    ///
+    /// Each register is written to its [`RegisterAddr::command_register`], so the measured position and
+    /// velocity become the command position and velocity, while read-only registers such as the
+    /// [`crate::registers::Fault`] are skipped. A command register that was read directly takes precedence
+    /// over one derived from a measurement. The [`crate::registers::Mode`] is written as reported, so check it
+    /// before sending, as a captured [`crate::registers::Modes::Fault`] can't be commanded.
+    /// Custom registers are skipped.
+    pub fn into_command_builder(self) -> FrameBuilder {
+        let mut builder = FrameBuilder::new();
+        let (direct, derived): (Vec<_>, Vec<_>) = self
+            .registers
+            .into_iter()
+            .filter_map(|reg| Some((reg.address.command_register()?, reg)))
+            .partition(|(address, reg)| *address == reg.address);
+        for (address, reg) in derived.into_iter().chain(direct) {
+            let _ = builder.add(RegisterData {
+                address,
+                resolution: reg.resolution,
+                data: reg.data,
+            });
+        }
+        builder
+    }
+
+    /// Returns `true` if the response frame contains no registers, as is the case for a zero-length reply.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.custom.is_empty()
+    }
+
+    /// Get the [`crate::registers::QCurrent`] and [`crate::registers::DCurrent`] (in amps) from the response frame, as `(q, d)`.
+    /// If either register is not found in the response frame [`None`] is returned.
+    pub fn currents(&self) -> Option<(f32, f32)> {
+        let q = self.get::<crate::registers::QCurrent>()?;
+        let d = self.get::<crate::registers::DCurrent>()?;
+        Some((q.value(), d.value()))
+    }
+
+    /// Get the [`crate::registers::PositionCommand`] (in Nm) from the response frame.
+    /// If the register is not found in the response frame [`None`] is returned.
+    ///
+    /// Three similarly named registers describe a position command at different stages:
+    ///  - [`crate::registers::CommandPosition`] is the position that was sent to the controller.
+    ///  - [`crate::registers::ControlPosition`] is the position the controller is currently tracking,
+    ///    which moves towards the command within the velocity and acceleration limits.
+    ///  - [`crate::registers::PositionCommand`] is the torque the position controller computes to track it,
+    ///    the sum of its proportional, integral, derivative and feedforward terms.
+    ///
+    /// Comparing the control position with the measured position, and this torque with the
+    /// [`crate::registers::Torque`], helps find where a command stops being followed.
+    pub fn position_command(&self) -> Option<f32> {
+        self.get::<crate::registers::PositionCommand>()
+            .map(|r| r.value())
+    }
+
+    /// Estimates the torque (in Nm) from the [`crate::registers::QCurrent`] as `kt * q_current`,
+    /// where `kt` is the torque constant of the motor in Nm/A at the output.
+    ///
+    /// `kt` is usually derived from the velocity constant of the motor as roughly `60 / (2π * Kv)`
+    /// with `Kv` in rpm/V, and must include any gear ratio between the rotor and the output.
+    /// If the [`crate::registers::QCurrent`] is not found in the response frame [`None`] is returned.
+    pub fn torque_from_current(&self, kt: f32) -> Option<f32> {
+        let q = self.get::<crate::registers::QCurrent>()?;
+        Some(kt * q.value())
+    }
+
+    /// Returns the estimate of [`ResponseFrame::torque_from_current`] minus the [`crate::registers::Torque`]
+    /// reported by the controller. The controller computes its torque from its own configuration (`motor.Kv`
+    /// and the rotor to output ratio), so a large difference means `kt` does not match that configuration.
+    /// If either register is not found in the response frame [`None`] is returned.
+    pub fn torque_discrepancy(&self, kt: f32) -> Option<f32> {
+        let torque = self.get::<crate::registers::Torque>()?;
+        Some(self.torque_from_current(kt)? - torque.value())
+    }
+
+    /// Get the [`crate::registers::Position`], [`crate::registers::Velocity`] and [`crate::registers::Torque`]
+    /// from the response frame, as `(position, velocity, torque)`.
+    /// If any of the registers is not found in the response frame [`None`] is returned.
+    pub fn pvt(&self) -> Option<(f32, f32, f32)> {
+        let position = self.get::<crate::registers::Position>()?;
+        let velocity = self.get::<crate::registers::Velocity>()?;
+        let torque = self.get::<crate::registers::Torque>()?;
+        Some((position.value(), velocity.value(), torque.value()))
+    }
+
+    /// Get the [`crate::registers::Aux1IndexRaw`] and [`crate::registers::Aux2IndexRaw`] from the response frame,
+    /// as `(aux1, aux2)`. If either register is not found in the response frame [`None`] is returned.
+    #[cfg(feature = "aux_index_raw")]
+    pub fn aux_index_raw(&self) -> Option<(i8, i8)> {
+        let aux1 = self.get::<crate::registers::Aux1IndexRaw>()?;
+        let aux2 = self.get::<crate::registers::Aux2IndexRaw>()?;
+        Some((aux1.value(), aux2.value()))
+    }
+
+    /// Get the raw data of a register listed in the [`CustomRegisters`] used to parse the frame.
+    /// If the address is not found in the response frame [`None`] is returned.
+    pub fn get_custom(&self, address: u16) -> Option<&CustomRegisterData> {
+        self.custom.iter().find(|reg| reg.address == address)
+    }
 }
 
 impl TryFrom<CanFdFrame> for ResponseFrame {
     type Error = FrameParseError;
 
     fn try_from(frame: CanFdFrame) -> Result<Self, Self::Error> {
-        let buf = frame.data;
-        ResponseFrame::from_bytes(&buf)
+        ResponseFrame::from_can_frame(&frame, &CustomRegisters::default())
+    }
+}
+
+/// The valid CAN-FD data lengths. Payloads are padded up to the next valid length.
+const CAN_FD_LENGTHS: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Returns the length of the CAN-FD data field that carries `len` bytes, or [`None`] if `len` is greater than 64.
+pub(crate) fn padded_len(len: usize) -> Option<usize> {
+    CAN_FD_LENGTHS.iter().find(|l| **l >= len).copied()
+}
+
+/// The bit rates of a CAN-FD bus, used to estimate how long a frame takes to transmit.
+///
+/// This does not change the timing of the bus, which is configured on the transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusTiming {
+    /// The bit rate of the arbitration phase in bits per second, typically 1 Mbps.
+    pub nominal_bps: u32,
+    /// The bit rate of the data phase in bits per second when BRS is used, typically 5 Mbps.
+    pub data_bps: u32,
+}
+
+impl BusTiming {
+    /// Creates a new [`BusTiming`]
+    pub fn new(nominal_bps: u32, data_bps: u32) -> Self {
+        Self {
+            nominal_bps,
+            data_bps,
+        }
+    }
+
+    /// Estimates the time on the wire of a CAN-FD frame (with an 11-bit identifier) carrying `payload_len` bytes.
+    ///
+    /// The payload is padded to the next valid CAN-FD length and the worst case number of stuff bits is assumed.
+    /// If `brs` is false the data phase is sent at the nominal bit rate.
+    pub fn tx_time(&self, payload_len: usize, brs: bool) -> Duration {
+        let len = padded_len(payload_len).unwrap_or(64) as u64;
+        // SOF, identifier, RRS, IDE, FDF, res, BRS
+        let header = 1 + 11 + 5;
+        // ACK slot, ACK delimiter, EOF and interframe space
+        let trailer = 1 + 1 + 7 + 3;
+        let nominal_bits = header + header / 4 + trailer;
+
+        // ESI, DLC and the data, which can all be stuffed
+        let stuffed = 1 + 4 + 8 * len;
+        let crc: u64 = if len > 16 { 21 } else { 17 };
+        // stuff count, CRC, fixed stuff bits and CRC delimiter
+        let crc_field = 4 + crc + (4 + crc).div_ceil(4) + 1;
+        let data_bits = stuffed + stuffed / 4 + crc_field;
+
+        let nominal_bps = self.nominal_bps.max(1) as f64;
+        let data_bps = if brs { self.data_bps } else { self.nominal_bps }.max(1) as f64;
+        Duration::from_secs_f64(nominal_bits as f64 / nominal_bps + data_bits as f64 / data_bps)
+    }
+}
+
+/// A subframe added with [`FrameBuilder::add_raw_subframe`], which is written without any checks
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RawSubFrame {
+    register: FrameRegisters,
+    len: u8,
+    base: u16,
+    data: Vec<u8>,
+}
+
+impl RawSubFrame {
+    /// Appends the bytes of the subframe to `buf`, encoding the length the same way as a [`SubFrame`]
+    fn as_bytes_into(&self, buf: &mut Vec<u8>) {
+        if (1..4).contains(&self.len) {
+            buf.push((self.register as u8) | self.len);
+        } else {
+            buf.push(self.register as u8);
+            buf.push(self.len);
+        }
+        buf.extend(varuint(self.base));
+        buf.extend_from_slice(&self.data);
     }
 }
 
@@ -171,23 +574,47 @@ impl TryFrom<CanFdFrame> for ResponseFrame {
 #[derive(Debug, PartialEq)]
 pub struct Frame {
     subframes: Vec<SubFrame>,
+    raw: Vec<RawSubFrame>,
 }
 
 impl Frame {
+    #[cfg(feature = "testing")]
+    pub(crate) fn subframes(&self) -> &[SubFrame] {
+        &self.subframes
+    }
+
     pub(crate) fn as_bytes(&self) -> Result<Vec<u8>, FrameError> {
-        let mut buf = Vec::new();
+        let mut buf = Vec::with_capacity(64);
+        self.as_bytes_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Appends the bytes of the frame to `buf`, so a buffer can be reused between frames.
+    pub(crate) fn as_bytes_into(&self, buf: &mut Vec<u8>) -> Result<(), FrameError> {
         for subframe in &self.subframes {
-            buf.extend(subframe.as_bytes()?);
+            subframe.as_bytes_into(buf)?;
         }
-        Ok(buf)
+        for subframe in &self.raw {
+            subframe.as_bytes_into(buf);
+        }
+        Ok(())
+    }
+
+    /// Estimates the time taken to transmit the frame with BRS enabled, see [`BusTiming::tx_time`].
+    pub fn estimated_tx_time(&self, timing: &BusTiming) -> Duration {
+        self.tx_time(timing, true)
+    }
+
+    pub(crate) fn tx_time(&self, timing: &BusTiming, brs: bool) -> Duration {
+        // `as_bytes` cannot fail for frames created with a `FrameBuilder`
+        let len = self.as_bytes().map(|b| b.len()).unwrap_or_default();
+        timing.tx_time(len, brs)
     }
 
     /// As building frames with multiple resolutions and read/write operations is complex,
     /// a [`FrameBuilder`] is provided to simplify the process.
     pub fn builder() -> FrameBuilder {
-        FrameBuilder {
-            registers: HashMap::new(),
-        }
+        FrameBuilder::new()
     }
 
     /// Quickly create and alter a [`FrameBuilder`] with a closure
@@ -217,12 +644,67 @@ impl Frame {
 /// Registers can be added in any order, and the builder will sort them into subframes.
 /// Multiple [`FrameBuilder`]s can be merged together.
 /// Duplicate registers are overwritten without warning.
-#[derive(Debug, PartialEq, Clone)]
+///
+/// With the `serde` feature a [`FrameBuilder`] can be serialized as the list of its registers, keeping the
+/// data of each register byte for byte, to record commands and replay them later. The length of the data is
+/// checked against the resolution of each register when deserializing.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SerializedFrameBuilder", try_from = "SerializedFrameBuilder")
+)]
 pub struct FrameBuilder {
-    registers: HashMap<FrameRegisters, HashMap<RegisterAddr, RegisterData>>,
+    registers: BTreeMap<FrameRegisters, BTreeMap<RegisterAddr, RegisterData>>,
+    raw: Vec<RawSubFrame>,
+}
+
+/// The serialized form of a [`FrameBuilder`]
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedFrameBuilder {
+    registers: Vec<RegisterData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    raw: Vec<RawSubFrame>,
+}
+
+#[cfg(feature = "serde")]
+impl From<FrameBuilder> for SerializedFrameBuilder {
+    fn from(builder: FrameBuilder) -> Self {
+        SerializedFrameBuilder {
+            registers: builder
+                .registers
+                .into_values()
+                .flat_map(BTreeMap::into_values)
+                .collect(),
+            raw: builder.raw,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedFrameBuilder> for FrameBuilder {
+    type Error = crate::RegisterError;
+
+    fn try_from(serialized: SerializedFrameBuilder) -> Result<Self, Self::Error> {
+        let mut builder = FrameBuilder::new();
+        for reg in serialized.registers {
+            let _ = builder.add(RegisterData::new(reg.address, reg.resolution, reg.data)?);
+        }
+        builder.raw = serialized.raw;
+        Ok(builder)
+    }
 }
 
 impl FrameBuilder {
+    /// Creates an empty [`FrameBuilder`], the same as [`Frame::builder`]. Does not allocate until a register is added.
+    pub const fn new() -> Self {
+        FrameBuilder {
+            registers: BTreeMap::new(),
+            raw: Vec::new(),
+        }
+    }
+
     fn frame_register(resolution: Resolution, read: bool) -> FrameRegisters {
         match (resolution, read) {
             (Resolution::Int8, true) => FrameRegisters::ReadInt8,
@@ -272,8 +754,131 @@ impl FrameBuilder {
         self
     }
 
+    /// Add every register of an iterator to the frame.
+    ///
+    /// ### Example
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use moteus::*;
+    /// # use moteus::registers::{RegisterData, Readable, Writeable};
+    /// let mut builder = Frame::builder();
+    /// let registers: Vec<RegisterData> = vec![
+    ///     registers::Mode::write(registers::Modes::Position)?.into(),
+    ///     registers::CommandPosition::write(0.0)?.into(),
+    ///     registers::Position::read().into(),
+    /// ];
+    /// builder.add_all(registers);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_all<R: Into<RegisterData>>(
+        &mut self,
+        regs: impl IntoIterator<Item = R>,
+    ) -> &mut Self {
+        regs.into_iter().for_each(|reg| {
+            let _ = self.add(reg);
+        });
+        self
+    }
+
+    /// Adds a subframe written exactly as given, for probing undocumented registers or firmware behaviour.
+    ///
+    /// **Advanced:** the subframe bypasses the register types entirely. It is written after the typed
+    /// subframes as `register` with `len` values starting at `base_addr`, followed by `data` unchanged,
+    /// so nothing checks that `len` and `data` agree with the resolution of `register`.
+    /// A malformed subframe may be rejected or misread by the controller, and raw subframes are not seen by
+    /// checks such as [`crate::Controller::set_fault_guard`] or [`crate::Controller::set_max_torque`].
+    ///
+    /// ```rust
+    /// # use moteus::{Frame, registers::FrameRegisters};
+    /// let mut builder = Frame::builder();
+    /// // write 2 Int8 values from the undocumented address 0x200
+    /// builder.add_raw_subframe(FrameRegisters::WriteInt8, 0x200, 2, &[1, 2]);
+    /// ```
+    pub fn add_raw_subframe(
+        &mut self,
+        register: FrameRegisters,
+        base_addr: u16,
+        len: u8,
+        data: &[u8],
+    ) -> &mut Self {
+        self.raw.push(RawSubFrame {
+            register,
+            len,
+            base: base_addr,
+            data: data.to_vec(),
+        });
+        self
+    }
+
+    /// Sets the resolution of every register read by the builder, moving them into the subframes of that resolution.
+    ///
+    /// Written registers keep their resolution, as their data is already encoded for it,
+    /// and raw subframes are unchanged. See [`crate::ResolutionPolicy`] to override the resolution by quantity.
+    ///
+    /// ```rust
+    /// # use moteus::{FrameBuilder, Resolution};
+    /// # use moteus::frame::Query;
+    /// let query = FrameBuilder::from(Query::default()).with_resolution(Resolution::Int16);
+    /// ```
+    pub fn with_resolution(self, resolution: Resolution) -> Self {
+        self.map_read_resolution(|_| resolution)
+    }
+
+    /// Sets the resolution of each register read by the builder to the one returned by `resolution`.
+    /// Written registers and raw subframes are unchanged.
+    pub(crate) fn map_read_resolution(
+        self,
+        resolution: impl Fn(&RegisterData) -> Resolution,
+    ) -> Self {
+        let mut builder = FrameBuilder {
+            registers: BTreeMap::new(),
+            raw: self.raw,
+        };
+        for reg in self.registers.into_values().flat_map(BTreeMap::into_values) {
+            let resolution = match reg.data {
+                None => resolution(&reg),
+                Some(_) => reg.resolution,
+            };
+            let _ = builder.add(RegisterData { resolution, ..reg });
+        }
+        builder
+    }
+
+    /// Removes every register which is read, keeping the writes and raw subframes.
+    pub(crate) fn without_reads(mut self) -> Self {
+        self.registers.retain(|_, regs| {
+            regs.retain(|_, reg| reg.data.is_some());
+            !regs.is_empty()
+        });
+        self
+    }
+
+    /// Returns an iterator over every register in the builder, in no particular order.
+    pub(crate) fn registers(&self) -> impl Iterator<Item = &RegisterData> {
+        self.registers.values().flat_map(|regs| regs.values())
+    }
+
+    /// Removes the register at `address`, whether it is read or written.
+    pub(crate) fn remove(&mut self, address: RegisterAddr) {
+        self.registers.retain(|_, regs| {
+            let _ = regs.remove(&address);
+            !regs.is_empty()
+        });
+    }
+
+    /// Returns the value written to the register `R`, if the builder writes to it.
+    pub(crate) fn get_write<R: Register>(&self) -> Option<Res<R>> {
+        self.registers
+            .values()
+            .filter_map(|regs| regs.get(&R::address()))
+            .find(|reg| reg.data.is_some())
+            .and_then(|reg| reg.as_res::<R>().ok())
+    }
+
     /// Merge two [`FrameBuilder`]s together
     pub fn merge(mut self, other: Self) -> Self {
+        self.raw.extend(other.raw);
         other.registers.into_iter().for_each(|(register, regs)| {
             let Some(existing) = self.registers.get_mut(&register) else {
                 let _ = self.registers.insert(register, regs);
@@ -284,6 +889,29 @@ impl FrameBuilder {
         self
     }
 
+    /// Merges the registers of `other` into this builder in place, without consuming either.
+    ///
+    /// The non-consuming counterpart of [`FrameBuilder::merge`], to add to a base builder kept across loops
+    /// without cloning it. Registers of `other` overwrite those already in the builder.
+    ///
+    /// ```rust
+    /// # use moteus::FrameBuilder;
+    /// # use moteus::frame::{Position, Query};
+    /// let base = FrameBuilder::from(Query::default());
+    /// let mut command = FrameBuilder::from(Position::hold());
+    /// command.merge_from(&base);
+    /// ```
+    pub fn merge_from(&mut self, other: &Self) -> &mut Self {
+        self.raw.extend(other.raw.iter().cloned());
+        for (register, regs) in &other.registers {
+            self.registers
+                .entry(*register)
+                .or_default()
+                .extend(regs.iter().map(|(addr, reg)| (*addr, reg.clone())));
+        }
+        self
+    }
+
     #[allow(clippy::unwrap_used)]
     /// Build the frame
     pub fn build(self) -> Frame {
@@ -294,19 +922,19 @@ impl FrameBuilder {
             .flat_map(|(frame_register, regs)| {
                 let mut subframes = Vec::new();
                 let mut regs: Vec<(RegisterAddr, RegisterData)> = regs.into_iter().collect();
-                regs.sort_by_key(|(k, _)| *k as u8);
+                regs.sort_by_key(|(k, _)| *k as u16);
                 let mut regs = regs.into_iter().peekable();
-                let mut base_reg = regs.peek().unwrap().0 as u8; // This `unwrap()` cannot fail when using pub API
+                let mut base_reg = regs.peek().unwrap().0 as u16; // This `unwrap()` cannot fail when using pub API
                 let mut reg_index = 0;
                 let mut subframe = SubFrame::new(frame_register, 0);
 
                 for (reg, value) in regs {
-                    if reg as u8 != base_reg + reg_index {
+                    if reg as u16 != base_reg + reg_index as u16 {
                         subframe.len = reg_index;
                         subframes.push(subframe);
                         reg_index = 0;
                         subframe = SubFrame::new(frame_register, 0);
-                        base_reg = reg as u8;
+                        base_reg = reg as u16;
                     }
                     subframe.add(value).unwrap(); // This `unwrap()` cannot fail when using pub API
                     reg_index += 1;
@@ -317,7 +945,24 @@ impl FrameBuilder {
                 subframes
             })
             .collect();
-        Frame { subframes }
+        Frame {
+            subframes,
+            raw: self.raw,
+        }
+    }
+}
+
+impl<R: Into<RegisterData>> Extend<R> for FrameBuilder {
+    fn extend<I: IntoIterator<Item = R>>(&mut self, iter: I) {
+        let _ = self.add_all(iter);
+    }
+}
+
+impl<R: Into<RegisterData>> FromIterator<R> for FrameBuilder {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut builder = FrameBuilder::new();
+        builder.extend(iter);
+        builder
     }
 }
 
@@ -327,6 +972,7 @@ mod tests {
     use super::*;
     use crate::protocol::registers;
     use crate::registers::{Faults, Readable, Writeable};
+    use crate::RegisterError;
 
     #[test]
     fn test_write_u8_subframe() {
@@ -432,13 +1078,10 @@ mod tests {
         assert_eq!(
             subframe.data,
             vec![
-                registers::Voltage::write_with_resolution(12.0, Resolution::Int8)
-                    .unwrap()
-                    .into(),
-                registers::Temperature::write_with_resolution(20.0, Resolution::Int8)
-                    .unwrap()
-                    .into(),
-                registers::Fault::write(Faults::Success).unwrap().into(),
+                RegisterData::encode::<registers::Voltage>(12.0, Resolution::Int8).unwrap(),
+                RegisterData::encode::<registers::Temperature>(20.0, Resolution::Int8).unwrap(),
+                RegisterData::encode::<registers::Fault>(Faults::Success, Resolution::Int8)
+                    .unwrap(),
             ]
         );
     }
@@ -460,15 +1103,9 @@ mod tests {
                 )
                 .unwrap()
                 .into(),
-                registers::Position::write_with_resolution(0.01, Resolution::Int16)
-                    .unwrap()
-                    .into(),
-                registers::Velocity::write_with_resolution(0.1, Resolution::Int16)
-                    .unwrap()
-                    .into(),
-                registers::Torque::write_with_resolution(-144.0, Resolution::Int16)
-                    .unwrap()
-                    .into(),
+                RegisterData::encode::<registers::Position>(0.01, Resolution::Int16).unwrap(),
+                RegisterData::encode::<registers::Velocity>(0.1, Resolution::Int16).unwrap(),
+                RegisterData::encode::<registers::Torque>(-144.0, Resolution::Int16).unwrap(),
             ]
         );
     }
@@ -491,6 +1128,578 @@ mod tests {
         ); //use the turbofish syntax when the type cannot be inferred.
     }
 
+    #[test]
+    fn parse_mode_only_response() {
+        let replies: [Vec<u8>; 4] = [
+            vec![0x21, 0x00, 0x0a],
+            vec![0x25, 0x00, 0x0a, 0x00],
+            vec![0x29, 0x00, 0x0a, 0x00, 0x00, 0x00],
+            [&[0x2d, 0x00][..], &10.0f32.to_le_bytes()].concat(),
+        ];
+        for reply in replies {
+            for padding in 0..3 {
+                let mut buf = reply.clone();
+                buf.resize(buf.len() + padding, 0x50);
+                let frame = ResponseFrame::from_bytes(&buf).unwrap();
+                assert_eq!(frame.registers.len(), 1);
+                assert_eq!(
+                    frame.get::<registers::Mode>().map(|r| r.value()),
+                    Some(registers::Modes::Position)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_torque_from_current() {
+        // Torque of 0.25Nm and QCurrent of 2.5A as Int16
+        let buf = vec![0x26, 0x03, 25, 0x00, 25, 0x00];
+        let response = ResponseFrame::from_bytes(&buf).unwrap();
+        assert_eq!(response.torque_from_current(0.1), Some(0.1 * 2.5));
+        assert!(response.torque_discrepancy(0.1).unwrap().abs() < 1e-6);
+        assert!(response.torque_discrepancy(0.2).unwrap() > 0.2);
+        let response = ResponseFrame::from_bytes(&[0x21, 0x00, 0x0a]).unwrap();
+        assert_eq!(response.torque_from_current(0.1), None);
+    }
+
+    #[test]
+    fn test_with_resolution() {
+        let mut builder = Frame::builder();
+        builder
+            .add(registers::Mode::write(registers::Modes::Stopped).unwrap())
+            .add(registers::Mode::read())
+            .add(registers::Position::read())
+            .add(registers::Velocity::read_with_resolution(Resolution::Int8));
+        // Mode, Position and Velocity are read in a single Int16 subframe
+        assert_eq!(
+            builder
+                .with_resolution(Resolution::Int16)
+                .build()
+                .as_bytes()
+                .unwrap(),
+            vec![0x01, 0x00, 0x00, 0x17, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let mut base = Frame::builder();
+        base.add(registers::Position::read()).add_raw_subframe(
+            FrameRegisters::ReadF32,
+            0x003,
+            1,
+            &[],
+        );
+        let mut command = Frame::builder();
+        command
+            .add(registers::Mode::write(registers::Modes::Stopped).unwrap())
+            .add(registers::Position::read_with_resolution(Resolution::Float));
+        let merged = command.clone().merge(base.clone());
+        command.merge_from(&base);
+        assert_eq!(command, merged);
+        assert_eq!(base.registers().count(), 1);
+    }
+
+    #[test]
+    fn test_raw_subframe() {
+        let mut builder = Frame::builder();
+        builder
+            .add(registers::Mode::write(registers::Modes::Stopped).unwrap())
+            .add_raw_subframe(FrameRegisters::WriteInt8, 0x200, 2, &[1, 2])
+            .add_raw_subframe(FrameRegisters::ReadF32, 0x003, 5, &[]);
+        assert_eq!(
+            builder.build().as_bytes().unwrap(),
+            vec![0x01, 0x00, 0x00, 0x02, 0x80, 0x04, 1, 2, 0x1c, 5, 0x03]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut builder = Frame::builder();
+        builder
+            .add(registers::Mode::write(registers::Modes::Position).unwrap())
+            .add(
+                registers::CommandPosition::write_with_resolution(0.25, Resolution::Int16).unwrap(),
+            )
+            .add(registers::Position::read())
+            .add_raw_subframe(FrameRegisters::WriteInt8, 0x200, 1, &[7]);
+        let json = serde_json::to_string(&builder).unwrap();
+        let restored: FrameBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, builder);
+        assert_eq!(
+            restored.build().as_bytes().unwrap(),
+            builder.build().as_bytes().unwrap()
+        );
+
+        // an Int16 register with a single byte of data
+        let json =
+            r#"{"registers":[{"address":"CommandPosition","resolution":"Int16","data":[1]}]}"#;
+        assert!(serde_json::from_str::<FrameBuilder>(json).is_err());
+    }
+
+    #[test]
+    fn test_into_command_builder() {
+        let mut buf = vec![0x21, 0x00, 0x0a]; // Mode as Int8
+        buf.extend([0x26, 0x01, 100, 0x00, 0x90, 0x01]); // Position and Velocity as Int16
+        buf.extend([0x21, 0x0f, 0x00]); // Fault as Int8
+        buf.extend([0x25, 0x21, 0x10, 0x00]); // CommandVelocity as Int16
+        let builder = ResponseFrame::from_bytes(&buf)
+            .unwrap()
+            .into_command_builder();
+        assert_eq!(
+            builder.get_write::<registers::Mode>().unwrap().value(),
+            registers::Modes::Position
+        );
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandPosition>()
+                .unwrap()
+                .value(),
+            0.01
+        );
+        // the CommandVelocity read directly is kept over the measured Velocity
+        assert_eq!(
+            builder
+                .get_write::<registers::CommandVelocity>()
+                .unwrap()
+                .value(),
+            0.004
+        );
+        assert!(builder.get_write::<registers::Fault>().is_none());
+        assert_eq!(builder.registers().count(), 3);
+    }
+
+    #[test]
+    fn parse_mixed_resolution_response() {
+        let mut buf = vec![0x21, 0x00, 0x0a]; // Mode as Int8
+        buf.extend([0x26, 0x01, 100, 0x00, 0x90, 0x01]); // Position and Velocity as Int16
+        buf.extend([0x2d, 0x03]); // Torque as Float
+        buf.extend(1.5f32.to_le_bytes());
+        buf.extend([0x23, 0x0d, 24, 20, 0x00]); // Voltage, Temperature and Fault as Int8
+        buf.extend([0x50, 0x50]);
+        let frame = ResponseFrame::from_bytes(&buf).unwrap();
+        assert_eq!(frame.registers.len(), 7);
+        assert_eq!(
+            frame.get::<registers::Mode>().map(|r| r.value()),
+            Some(registers::Modes::Position)
+        );
+        assert_eq!(frame.pvt(), Some((0.01, 0.1, 1.5)));
+        assert_eq!(
+            frame.get::<registers::Voltage>().map(|r| r.value()),
+            Some(12.0)
+        );
+        assert_eq!(
+            frame.get::<registers::Temperature>().map(|r| r.value()),
+            Some(20.0)
+        );
+        assert_eq!(
+            frame.get::<registers::Fault>().map(|r| r.value()),
+            Some(Faults::Success)
+        );
+    }
+
+    #[test]
+    fn parse_address_overflow() {
+        // the last register of a subframe starting at 0xffff would be past the end of the address space
+        let mut custom = CustomRegisters::new();
+        custom.add(0xffff, "last");
+        let buf = [0x22, 0xff, 0xff, 0x03, 0x00, 0x00];
+        let error = ResponseFrame::from_bytes_with(&buf, &custom).unwrap_err();
+        assert!(matches!(
+            error.root(),
+            FrameParseError::AddressOutOfRange(0x10000)
+        ));
+    }
+
+    #[test]
+    fn parse_mode_only_response_len_in_next_byte() {
+        let buf = vec![0x20, 0x01, 0x00, 0x0a];
+        let (subframe, consumed) = SubFrame::from_bytes(&buf).unwrap();
+        assert_eq!(consumed, 4);
+        assert_eq!(subframe.unwrap().len, 1);
+    }
+
+    #[test]
+    fn parse_strict() {
+        // Mode and Position, padded from 9 to 12 bytes
+        let buf = [&[0x21, 0x00, 0x0a, 0x2d, 0x01][..], &[0x00; 4], &[0x50; 3]].concat();
+        let frame = ResponseFrame::from_bytes_strict(&buf).unwrap();
+        assert_eq!(frame, ResponseFrame::from_bytes(&buf).unwrap());
+
+        let root = |buf: &[u8]| match ResponseFrame::from_bytes_strict(buf).unwrap_err().root() {
+            FrameParseError::NonConforming(reason) => *reason,
+            e => panic!("unexpected error: {e}"),
+        };
+        // 9 bytes is not a CAN-FD length
+        assert!(ResponseFrame::from_bytes(&buf[..9]).is_ok());
+        assert_eq!(
+            root(&buf[..9]),
+            "frame is not padded to the CAN-FD length of its data"
+        );
+        // padded past the next CAN-FD length
+        assert_eq!(
+            root(&[&buf[..], &[0x50; 4]].concat()),
+            "frame is not padded to the CAN-FD length of its data"
+        );
+        assert_eq!(root(&[0x20, 0x00, 0x00]), "empty subframe");
+        assert_eq!(
+            root(&[0x21, 0x00, 0x00, 0x21, 0x00, 0x00]),
+            "register reported more than once"
+        );
+        assert_eq!(
+            root(&[0x21, 0x00, 0x00, 0x50, 0x21, 0x0f, 0x00, 0x50]),
+            "data after the padding of the frame"
+        );
+
+        // a read echoed back is accepted by the lenient parser
+        assert!(ResponseFrame::from_bytes(&[0x11, 0x00]).is_ok());
+        assert!(matches!(
+            ResponseFrame::from_bytes_strict(&[0x11, 0x00])
+                .unwrap_err()
+                .root(),
+            FrameParseError::UnsupportedSubframeRegister(FrameRegisters::ReadInt8)
+        ));
+    }
+
+    #[test]
+    fn parse_empty_response() {
+        let (subframe, consumed) = SubFrame::from_bytes(&[]).unwrap();
+        assert!(subframe.is_none());
+        assert_eq!(consumed, 0);
+
+        let frame = ResponseFrame::try_from(CanFdFrame {
+            arbitration_id: 0x0100,
+            data: Vec::new(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(frame.is_empty());
+        assert_eq!(frame.get::<registers::Mode>(), None);
+    }
+
+    #[test]
+    fn parse_currents() {
+        // QCurrent and DCurrent as Int16
+        let buf = vec![0x26, 0x04, 0x0f, 0x00, 0xfb, 0xff];
+        let frame = ResponseFrame::from_bytes(&buf).unwrap();
+        let (q, d) = frame.currents().unwrap();
+        assert!((q - 1.5).abs() < 1e-6);
+        assert!((d + 0.5).abs() < 1e-6);
+
+        let frame = ResponseFrame::from_bytes(&[0x21, 0x04, 0x02]).unwrap();
+        assert_eq!(frame.currents(), None);
+    }
+
+    #[test]
+    fn parse_pvt() {
+        // Position, Velocity and Torque as Float
+        let buf = [
+            &[0x2f, 0x01][..],
+            &0.25f32.to_le_bytes(),
+            &(-1.0f32).to_le_bytes(),
+            &0.5f32.to_le_bytes(),
+        ]
+        .concat();
+        let frame = ResponseFrame::from_bytes(&buf).unwrap();
+        assert_eq!(frame.pvt(), Some((0.25, -1.0, 0.5)));
+
+        // Torque missing
+        let mut buf = buf[..10].to_vec();
+        buf[0] = 0x2e;
+        let frame = ResponseFrame::from_bytes(&buf).unwrap();
+        assert_eq!(frame.pvt(), None);
+    }
+
+    #[test]
+    fn parse_truncated() {
+        // Mode (Int8), Position, Velocity (Int16)
+        let buf = vec![0x21, 0x00, 0x0a, 0x26, 0x01, 0x64, 0x00, 0xf6, 0xff];
+        for len in [1, 2, 4, 5, 6, 7, 8] {
+            let err = ResponseFrame::from_bytes(&buf[..len]).unwrap_err();
+            assert!(
+                matches!(err.root(), FrameParseError::Truncated),
+                "len {len}"
+            );
+        }
+        // the second subframe starts at byte 3
+        assert!(matches!(
+            ResponseFrame::from_bytes(&buf[..5]),
+            Err(FrameParseError::Subframe {
+                index: 1,
+                offset: 3,
+                ..
+            })
+        ));
+        // the length in the next byte is missing
+        assert!(matches!(
+            ResponseFrame::from_bytes(&[0x24]).unwrap_err().root(),
+            FrameParseError::Truncated
+        ));
+        // the varuint address is not terminated
+        assert!(matches!(
+            ResponseFrame::from_bytes(&[0x21, 0x82]).unwrap_err().root(),
+            FrameParseError::Truncated
+        ));
+    }
+
+    #[test]
+    fn length_encodings() {
+        // 3 registers fit in the low bits, 4 need the length in the next byte
+        for (len, header) in [(3, vec![0x1f, 0x01]), (4, vec![0x1c, 0x04, 0x01])] {
+            let frame = Frame::with_builder(|b| {
+                b.add(registers::Position::read_with_resolution(Resolution::Float))
+                    .add(registers::Velocity::read_with_resolution(Resolution::Float))
+                    .add(registers::Torque::read_with_resolution(Resolution::Float));
+                if len == 4 {
+                    b.add(registers::QCurrent::read_with_resolution(Resolution::Float));
+                }
+            })
+            .build();
+            assert_eq!(frame.as_bytes().unwrap(), header);
+        }
+
+        // the C++ library may use the next byte form for lengths that fit in the low bits
+        let short = [&[0x2d, 0x01][..], &1.0f32.to_le_bytes()].concat();
+        let long = [&[0x2c, 0x01, 0x01][..], &1.0f32.to_le_bytes()].concat();
+        for buf in [short, long] {
+            let (subframe, len) = SubFrame::from_bytes(&buf).unwrap();
+            assert_eq!(len, buf.len());
+            let subframe = subframe.unwrap();
+            assert_eq!(subframe.len, 1);
+            assert_eq!(subframe.data.len(), 1);
+            assert_eq!(subframe.data[0].address, RegisterAddr::Position);
+        }
+
+        // exactly 4 registers with the length in the next byte
+        let mut buf = vec![0x24, 0x04, 0x20];
+        buf.extend([0x01, 0x00].repeat(4));
+        let (subframe, len) = SubFrame::from_bytes(&buf).unwrap();
+        assert_eq!(len, buf.len());
+        assert_eq!(subframe.unwrap().data.len(), 4);
+    }
+
+    #[test]
+    fn varuint_address_round_trip() {
+        let frame = Frame::with_builder(|b| {
+            b.add(registers::RegisterMapVersion::read());
+            b.add(registers::Velocity::read_with_resolution(Resolution::Int32));
+        })
+        .build();
+        // 0x102 and 0x002 are not contiguous, and 0x102 is encoded in 2 bytes
+        assert_eq!(
+            frame.as_bytes().unwrap(),
+            vec![0x19, 0x02, 0x19, 0x82, 0x02]
+        );
+
+        let buf = [
+            &[0x29, 0x82, 0x02][..],
+            &4u32.to_le_bytes(),
+            &[0x21, 0x00, 0x0a],
+        ]
+        .concat();
+        let response = ResponseFrame::from_bytes(&buf).unwrap();
+        assert_eq!(
+            response
+                .get::<registers::RegisterMapVersion>()
+                .map(|r| r.value()),
+            Some(4)
+        );
+        assert_eq!(
+            response.get::<registers::Mode>().map(|r| r.value()),
+            Some(registers::Modes::Position)
+        );
+
+        // a complete subframe whose length or address does not fit is out of range, not truncated
+        assert!(matches!(
+            ResponseFrame::from_bytes(&[0x20, 0x80, 0x02, 0x00])
+                .unwrap_err()
+                .root(),
+            FrameParseError::LengthOutOfRange(0x100)
+        ));
+        assert!(matches!(
+            ResponseFrame::from_bytes(&[0x21, 0x80, 0x80, 0x04, 0x00])
+                .unwrap_err()
+                .root(),
+            FrameParseError::AddressOutOfRange(0x10000)
+        ));
+    }
+
+    #[test]
+    fn parse_random_bytes() {
+        // a simple xorshift so the test is deterministic
+        let mut state = 0x2545_f491_u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for _ in 0..10_000 {
+            let len = next() as usize % 16;
+            let buf: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            let _ = ResponseFrame::from_bytes(&buf);
+        }
+    }
+
+    #[test]
+    fn response_used_brs() {
+        let frame = CanFdFrame {
+            data: vec![0x21, 0x00, 0x0a],
+            brs: Some(true),
+            ..Default::default()
+        };
+        let response = ResponseFrame::try_from(frame).unwrap();
+        assert_eq!(response.used_brs(), Some(true));
+        let response = ResponseFrame::from_bytes(&[0x21, 0x00, 0x0a]).unwrap();
+        assert_eq!(response.used_brs(), None);
+    }
+
+    #[test]
+    fn response_to_map() {
+        // Mode (Int8), Position, Velocity (Int16)
+        let buf = vec![0x21, 0x00, 0x0a, 0x26, 0x01, 0x64, 0x00, 0xf6, 0xff];
+        let map = ResponseFrame::from_bytes(&buf).unwrap().to_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            map[&RegisterAddr::Mode],
+            RegisterValue::Mode(registers::Modes::Position)
+        );
+        assert_eq!(map[&RegisterAddr::Position], RegisterValue::Float(0.01));
+        assert!((map[&RegisterAddr::Velocity].as_f64() + 0.0025).abs() < 1e-6);
+    }
+
+    #[test]
+    fn response_diff() {
+        // Mode, Position (Float)
+        let old = [&[0x21, 0x00, 0x0a, 0x2d, 0x01][..], &f32::NAN.to_le_bytes()].concat();
+        let old = ResponseFrame::from_bytes(&old).unwrap();
+        assert!(old.diff(&old).is_empty());
+
+        // Mode changed, Position missing and Velocity added
+        let new = [&[0x21, 0x00, 0x00, 0x2d, 0x02][..], &1.0f32.to_le_bytes()].concat();
+        let new = ResponseFrame::from_bytes(&new).unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(diff.len(), 3);
+        assert_eq!(
+            diff[0],
+            (
+                RegisterAddr::Mode,
+                Some(RegisterValue::Mode(registers::Modes::Position)),
+                Some(RegisterValue::Mode(registers::Modes::Stopped))
+            )
+        );
+        assert_eq!(diff[1].0, RegisterAddr::Position);
+        assert_eq!(diff[1].2, None);
+        assert_eq!(
+            diff[2],
+            (
+                RegisterAddr::Velocity,
+                None,
+                Some(RegisterValue::Float(1.0))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_custom_register() {
+        // Mode (Int8) followed by an unknown register at 0x007
+        let buf = vec![0x21, 0x00, 0x0a, 0x21, 0x07, 0x05];
+        assert!(matches!(
+            ResponseFrame::from_bytes(&buf),
+            Err(FrameParseError::Subframe { index: 1, offset: 3, ref source })
+                if matches!(**source, FrameParseError::RegisterError(RegisterError::InvalidAddress(0x007)))
+        ));
+
+        let mut custom = CustomRegisters::new();
+        custom.add(0x007, "Custom");
+        let frame = ResponseFrame::from_bytes_with(&buf, &custom).unwrap();
+        assert_eq!(
+            frame.get::<registers::Mode>().map(|r| r.value()),
+            Some(registers::Modes::Position)
+        );
+        assert_eq!(
+            frame.get_custom(0x007),
+            Some(&CustomRegisterData {
+                address: 0x007,
+                resolution: Resolution::Int8,
+                data: vec![0x05],
+            })
+        );
+    }
+
+    #[test]
+    fn test_collect_builder() {
+        let registers: Vec<RegisterData> = vec![
+            registers::Mode::write(registers::Modes::Position)
+                .unwrap()
+                .into(),
+            registers::CommandPosition::write(1.0).unwrap().into(),
+        ];
+        let collected: FrameBuilder = registers.clone().into_iter().collect();
+        let mut extended = Frame::builder();
+        extended.extend(registers.clone());
+        let mut added = Frame::builder();
+        let _ = added.add_all(registers);
+        assert_eq!(collected, extended);
+        assert_eq!(collected, added);
+        assert_eq!(collected.registers().count(), 2);
+        assert!(collected.get_write::<registers::Mode>().is_some());
+    }
+
+    #[test]
+    fn test_empty_builder() {
+        const EMPTY: FrameBuilder = FrameBuilder::new();
+        assert_eq!(EMPTY, FrameBuilder::default());
+        assert_eq!(EMPTY, Frame::builder());
+        assert_eq!(EMPTY.registers().count(), 0);
+    }
+
+    #[test]
+    fn test_padded_len() {
+        assert_eq!(padded_len(0), Some(0));
+        assert_eq!(padded_len(8), Some(8));
+        assert_eq!(padded_len(9), Some(12));
+        assert_eq!(padded_len(33), Some(48));
+        assert_eq!(padded_len(64), Some(64));
+        assert_eq!(padded_len(65), None);
+    }
+
+    #[test]
+    fn test_tx_time() {
+        let timing = BusTiming::new(1_000_000, 5_000_000);
+        // 33 nominal bits and 679 data bits
+        assert_eq!(
+            timing.tx_time(64, true),
+            Duration::from_nanos(33_000 + 135_800)
+        );
+        assert_eq!(timing.tx_time(64, false), Duration::from_micros(33 + 679));
+        assert_eq!(timing.tx_time(9, true), timing.tx_time(12, true));
+        assert!(timing.tx_time(8, true) < timing.tx_time(12, true));
+
+        let frame = Frame::with_builder(|b| {
+            b.add(registers::Mode::write(registers::Modes::Stopped).unwrap());
+        })
+        .build();
+        assert_eq!(frame.estimated_tx_time(&timing), timing.tx_time(3, true));
+    }
+
+    #[test]
+    fn as_bytes_into_reuses_buffer() {
+        let frame = Frame::with_builder(|b| {
+            b.add(registers::Mode::write(registers::Modes::Stopped).unwrap());
+        })
+        .build();
+        let mut buf = Vec::with_capacity(64);
+        frame.as_bytes_into(&mut buf).unwrap();
+        frame.as_bytes_into(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [frame.as_bytes().unwrap(), frame.as_bytes().unwrap()].concat()
+        );
+        assert_eq!(buf.capacity(), 64);
+    }
+
     #[test]
     fn multi_subframes_into_bytes() {
         let mut builder = Frame::builder();
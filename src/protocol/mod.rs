@@ -1,10 +1,16 @@
+pub(crate) mod diagnostic;
 mod frame;
+mod policy;
 pub mod registers;
 
-pub use frame::{Frame, FrameBuilder, ResponseFrame};
+#[cfg(feature = "testing")]
+pub use frame::SubFrame;
+pub use frame::{BusTiming, Frame, FrameBuilder, ResponseFrame};
+pub use policy::ResolutionPolicy;
 
 /// Moteus register can be read in multiple resolutions (`Int8`, `Int16`, `Int32`, `Float`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Resolution {
     /// An 8-bit integer. Some registers expect a signed 8-bit integer, while others expect an unsigned 8-bit integer.
     Int8,
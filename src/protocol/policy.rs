@@ -0,0 +1,154 @@
+//! Overriding the resolution of register reads by physical quantity.
+
+use crate::protocol::registers::{
+    RegisterAddr, CURRENT_MAP, POSITION_MAP, TORQUE_MAP, VELOCITY_MAP, VOLTAGE_MAP,
+};
+use crate::protocol::FrameBuilder;
+use crate::registers::{Read, Readable};
+use crate::Resolution;
+
+/// Overrides the [`Resolution`] used to read registers, grouped by the physical quantity they represent.
+///
+/// Registers are grouped by their mapping, so for example every register using the position mapping
+/// (`Position`, `AbsPosition`, `ControlPosition`, ...) is read with the `position` resolution.
+/// The acceleration limit shares the velocity mapping and is grouped with velocity.
+/// Quantities left as `None`, and registers without a mapping, keep their requested resolution.
+///
+/// Set it on a [`crate::Controller`] with [`crate::Controller::set_resolution_policy`] to apply it to every frame sent,
+/// or apply it to a single frame with [`ResolutionPolicy::apply`].
+///
+/// ```rust
+/// # use moteus::{ResolutionPolicy, Resolution};
+/// # use moteus::frame::Query;
+/// let policy = ResolutionPolicy {
+///     position: Some(Resolution::Int16),
+///     velocity: Some(Resolution::Int16),
+///     ..Default::default()
+/// };
+/// let query = policy.apply(Query::default());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolutionPolicy {
+    /// The resolution of registers using the position mapping
+    pub position: Option<Resolution>,
+    /// The resolution of registers using the velocity mapping
+    pub velocity: Option<Resolution>,
+    /// The resolution of registers using the torque mapping
+    pub torque: Option<Resolution>,
+    /// The resolution of registers using the current mapping
+    pub current: Option<Resolution>,
+    /// The resolution of registers using the voltage mapping
+    pub voltage: Option<Resolution>,
+    /// The resolution of registers using the temperature mapping
+    pub temperature: Option<Resolution>,
+}
+
+impl ResolutionPolicy {
+    /// Creates a [`ResolutionPolicy`] which does not override any resolution
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the resolution set for the quantity of the register at `addr`, if any
+    pub fn resolution(&self, addr: RegisterAddr) -> Option<Resolution> {
+        let mapping = addr.mapping();
+        if mapping == POSITION_MAP {
+            self.position
+        } else if mapping == VELOCITY_MAP {
+            self.velocity
+        } else if mapping == TORQUE_MAP {
+            self.torque
+        } else if mapping == VOLTAGE_MAP {
+            self.voltage
+        } else if mapping == CURRENT_MAP {
+            // temperatures use the same scaling as currents
+            match addr {
+                RegisterAddr::Temperature | RegisterAddr::MotorTemperature => self.temperature,
+                _ => self.current,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a [`Read`] of `R`, with the resolution set by the policy or the default resolution of the register
+    pub fn read<R: Readable>(&self) -> Read<R> {
+        R::read_with_resolution(
+            self.resolution(R::address())
+                .unwrap_or(R::DEFAULT_RESOLUTION),
+        )
+    }
+
+    /// Returns the frame with the resolution of each read register set by the policy.
+    /// Written registers and raw subframes are left unchanged.
+    pub fn apply(&self, frame: impl Into<FrameBuilder>) -> FrameBuilder {
+        frame
+            .into()
+            .map_read_resolution(|reg| self.resolution(reg.address).unwrap_or(reg.resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::frame::Query;
+    use crate::protocol::registers::RegisterData;
+    use crate::registers::{self, Writeable};
+
+    #[test]
+    fn test_resolution() {
+        let policy = ResolutionPolicy {
+            position: Some(Resolution::Int16),
+            current: Some(Resolution::Int8),
+            temperature: Some(Resolution::Int32),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.resolution(RegisterAddr::AbsPosition),
+            Some(Resolution::Int16)
+        );
+        assert_eq!(
+            policy.resolution(RegisterAddr::QCurrent),
+            Some(Resolution::Int8)
+        );
+        assert_eq!(
+            policy.resolution(RegisterAddr::Temperature),
+            Some(Resolution::Int32)
+        );
+        assert_eq!(policy.resolution(RegisterAddr::Velocity), None);
+        assert_eq!(policy.resolution(RegisterAddr::Mode), None);
+        assert_eq!(
+            RegisterData::from(policy.read::<registers::Velocity>()).resolution,
+            Resolution::Float
+        );
+        assert_eq!(
+            RegisterData::from(policy.read::<registers::Position>()).resolution,
+            Resolution::Int16
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let policy = ResolutionPolicy {
+            position: Some(Resolution::Int16),
+            ..Default::default()
+        };
+        let mut frame: FrameBuilder = Query::default().into();
+        frame.add(registers::CommandPosition::write(1.0).unwrap());
+        let frame = policy.apply(frame);
+        let resolution = |addr| {
+            frame
+                .registers()
+                .find(|r| r.address == addr)
+                .map(|r| r.resolution)
+        };
+        assert_eq!(resolution(RegisterAddr::Position), Some(Resolution::Int16));
+        assert_eq!(resolution(RegisterAddr::Velocity), Some(Resolution::Float));
+        assert_eq!(resolution(RegisterAddr::Mode), Some(Resolution::Int8));
+        assert_eq!(
+            resolution(RegisterAddr::CommandPosition),
+            Some(Resolution::Float)
+        );
+    }
+}
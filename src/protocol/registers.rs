@@ -9,31 +9,38 @@ use crate::{RegisterError, Resolution};
 use byteorder::{ReadBytesExt, LE};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Duration;
 use zerocopy::AsBytes;
 
 /// Used to define a register with Integers as the representation
 macro_rules! int_rw_register {
-    (@IMPL_REG, $reg:ident : $addr:expr, $type:ty, $res:expr, $mapping:expr) => {
+    (@IMPL_ENCODE, $reg:ident : $addr:expr, $type:ty, $res:expr, $mapping:expr) => {
+        impl Encode for $reg {
+            fn encode(data: Self::INNER, r: Resolution) -> Result<Vec<u8>, RegisterError> {
+                encode_value(data, r, $mapping, stringify!($reg))
+            }
+        }
+    };
+    (@IMPL_WRITE, $reg:ident) => {
         impl Writeable for $reg {
             fn write_with_resolution(data: Self::INNER, r: Resolution) -> Result<Write<Self>, RegisterError> {
-                let bytes = match r {
-                    Resolution::Int8 => data.try_into_1_byte($mapping.0).map(|x| vec![x]),
-                    Resolution::Int16 => data.try_into_2_bytes($mapping.1).map(|x| x.to_vec()),
-                    Resolution::Int32 => data.try_into_4_bytes($mapping.2).map(|x| x.to_vec()),
-                    Resolution::Float => {
-                        data.try_into_f32_bytes().map(|x| x.to_vec())
-                    }
-                }?;
-
                 Ok(Write {
                     register: PhantomData,
                     resolution: r,
-                    data:bytes,
+                    data: Self::encode(data, r)?,
                 })
             }
         }
+        impl CommandRegister for $reg {}
+        impl Access for $reg {
+            const WRITEABLE: bool = true;
+        }
+    };
+    (@IMPL_READ, $reg:ident) => {
         impl Readable for $reg {
             fn read_with_resolution(r: Resolution) -> Read<Self> {
                 Read {
@@ -68,12 +75,19 @@ macro_rules! int_rw_register {
         }
     };
     (@INTERNAL, $reg:ident : $addr:expr, $type:ty, $res:expr, $mapping:expr) => {
-        int_rw_register!(@IMPL_REG, $reg : $addr, $type, $res, $mapping);
+        int_rw_register!(@INTERNAL_RO, $reg : $addr, $type, $res, $mapping);
+        int_rw_register!(@IMPL_WRITE, $reg);
+    };
+    (@INTERNAL_RO, $reg:ident : $addr:expr, $type:ty, $res:expr, $mapping:expr) => {
+        int_rw_register!(@IMPL_ENCODE, $reg : $addr, $type, $res, $mapping);
+        int_rw_register!(@IMPL_READ, $reg);
         int_rw_register!(@IMPL_REGISTER, $reg : $addr, $type, $res, $mapping);
     };
     ($reg:ident : $addr:expr, $type:ty, $res:expr) => {
         #[doc = concat!("Struct representing the ",stringify!($reg)," register at ",stringify!($addr)," .")]
         #[doc = concat!(stringify!($reg)," can be represented as larger ints but not floats or smaller ints")]
+        #[doc = ""]
+        #[doc = concat!("The default resolution is `", stringify!($res), "`. Writing a value outside the range of a smaller resolution returns [`RegisterError::ResolutionTooSmall`].")]
         #[derive(Clone, Debug, PartialEq)]
         pub struct $reg;
 
@@ -83,6 +97,24 @@ macro_rules! int_rw_register {
 
 }
 
+/// Used to define a read-only register with Integers as the representation
+macro_rules! int_ro_register {
+    ($reg:ident : $addr:expr, $type:ty, $res:expr) => {
+        #[doc = concat!("Struct representing the read-only ",stringify!($reg)," register at ",stringify!($addr)," .")]
+        #[doc = concat!(stringify!($reg)," can be represented as larger ints but not floats or smaller ints")]
+        #[doc = ""]
+        #[doc = concat!("The default resolution is `", stringify!($res), "`.")]
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $reg;
+
+        int_rw_register!(@INTERNAL_RO, $reg : $addr, $type, $res, NO_MAP);
+        impl StatusRegister for $reg {}
+        impl Access for $reg {
+            const WRITEABLE: bool = false;
+        }
+    };
+}
+
 /// Used to define a register with f32 as the representation.
 /// These registers using a `Map` to convert to different resolutions
 macro_rules! map_rw_register {
@@ -102,13 +134,35 @@ macro_rules! map_rw_register {
        map_rw_register!($reg : $addr, f32, Resolution::Float, $mapping);
     };
 }
+
+/// Used to define a read-only register with f32 as the representation, see [`map_rw_register`]
+macro_rules! map_ro_register {
+    ($reg:ident : $addr:expr, $type:ty, $res:expr, $mapping:expr) => {
+        #[derive(Clone, Debug, PartialEq)]
+        #[doc = concat!("Struct representing the read-only ",stringify!($reg)," register at ",stringify!($addr)," .")]
+        #[doc = concat!(stringify!($reg)," uses `", stringify!($mapping), "` to map between different resolutions")]
+        pub struct $reg {
+            value: Option<$type>,
+            resolution: Resolution,
+        }
+
+        int_rw_register!(@INTERNAL_RO, $reg : $addr, $type, $res, $mapping);
+        impl StatusRegister for $reg {}
+        impl Access for $reg {
+            const WRITEABLE: bool = false;
+        }
+    };
+    ($reg:ident : $addr:expr, $mapping:expr) => {
+       map_ro_register!($reg : $addr, f32, Resolution::Float, $mapping);
+    };
+}
 /// As the Moteus Registers are each a unique struct, they all implement the [`Register`] trait.
 pub trait Register {
     /// The inner type of the register
     type INNER: PartialEq + Debug;
     /// Each struct has a default [`Resolution`] that is used when writing to the register.
     const DEFAULT_RESOLUTION: Resolution;
-    /// The mapping used to
+    /// The [`Map`] used to scale the register at integer resolutions
     const MAPPING: Map;
     /// The name of the register for use in debugging/display
     const NAME: &'static str;
@@ -151,6 +205,26 @@ where
     data: Vec<u8>,
 }
 
+/// Encodes a value into the bytes of a register. Implemented for every register, including those that are not [`Writeable`],
+/// so the crate can build replies and runtime writes from a value.
+pub(crate) trait Encode: Register {
+    /// Encodes `data` at the resolution `r`, applying the [`Register::MAPPING`]
+    fn encode(data: Self::INNER, r: Resolution) -> Result<Vec<u8>, RegisterError>;
+}
+
+/// A register that commands or configures the controller. Every [`CommandRegister`] is [`Writeable`].
+pub trait CommandRegister: Writeable {}
+
+/// A register that only reports the state of the controller, such as [`Position`] or [`Fault`].
+///
+/// Status registers are [`Readable`] but not [`Writeable`], so writing one is a compile error.
+pub trait StatusRegister: Readable {}
+
+/// Whether a register is a [`CommandRegister`] or a [`StatusRegister`], for the runtime lookups of [`RegisterAddr`]
+pub(crate) trait Access: Register {
+    const WRITEABLE: bool;
+}
+
 /// All [`Register`]s that are writable impl the [`Readable`] trait
 pub trait Readable: Register {
     /// Returns a [`Read`] struct with the default resolution
@@ -166,6 +240,36 @@ pub trait Readable: Register {
         Self: Sized;
 }
 
+/// A [`Readable`] register, or a tuple of up to 6 of them, read with their default resolutions.
+/// Used by [`crate::Controller::query_with`].
+pub trait ReadSet {
+    /// Adds a read of each register to `builder`
+    fn add_reads(builder: &mut crate::FrameBuilder);
+}
+
+impl<R: Readable> ReadSet for R {
+    fn add_reads(builder: &mut crate::FrameBuilder) {
+        let _ = builder.add(R::read());
+    }
+}
+
+macro_rules! read_set_tuple {
+    ($($reg:ident),+) => {
+        impl<$($reg: Readable),+> ReadSet for ($($reg,)+) {
+            fn add_reads(builder: &mut crate::FrameBuilder) {
+                $(let _ = builder.add($reg::read());)+
+            }
+        }
+    };
+}
+
+read_set_tuple!(A);
+read_set_tuple!(A, B);
+read_set_tuple!(A, B, C);
+read_set_tuple!(A, B, C, D);
+read_set_tuple!(A, B, C, D, E);
+read_set_tuple!(A, B, C, D, E, F);
+
 /// holds the address and resolution to be read from the controller
 /// impls Into<[`RegisterData`]>
 #[derive(Debug, Clone)]
@@ -218,6 +322,7 @@ where
 
 /// A struct that represents the raw data (as `Vec<u8>`) that has been read from, or will be written to, a register
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegisterData {
     /// The [`RegisterAddr`] of the register
     pub address: RegisterAddr,
@@ -228,6 +333,25 @@ pub struct RegisterData {
 }
 
 impl RegisterData {
+    /// Creates a [`RegisterData`], checking that the length of `data` matches the size of the `resolution`.
+    /// Pass `None` as the data to read the register.
+    ///
+    /// Returns [`RegisterError::InvalidData`] if the length does not match.
+    pub fn new(
+        address: RegisterAddr,
+        resolution: Resolution,
+        data: Option<Vec<u8>>,
+    ) -> Result<RegisterData, RegisterError> {
+        if data.as_ref().is_some_and(|d| d.len() != resolution.size()) {
+            return Err(RegisterError::InvalidData);
+        }
+        Ok(RegisterData {
+            address,
+            resolution,
+            data,
+        })
+    }
+
     pub(crate) fn as_res<R: Register>(&self) -> Result<Res<R>, RegisterError> {
         let bytes = self.data.as_ref().ok_or(RegisterError::NoData)?;
         let value = R::from_bytes(bytes, self.resolution)?;
@@ -240,7 +364,7 @@ impl RegisterData {
         resolution: Resolution,
     ) -> Result<RegisterData, RegisterError> {
         Ok(RegisterData {
-            address: RegisterAddr::from_u16(addr).ok_or(RegisterError::InvalidAddress)?,
+            address: RegisterAddr::from_u16(addr).ok_or(RegisterError::InvalidAddress(addr))?,
             resolution,
             data: Some(bytes.into()),
         })
@@ -270,6 +394,21 @@ where
     }
 }
 
+impl RegisterData {
+    /// Encodes `value` into the register `R`, which need not be [`Writeable`].
+    /// Used to build the replies of the controller.
+    pub(crate) fn encode<R: Encode>(
+        value: R::INNER,
+        resolution: Resolution,
+    ) -> Result<RegisterData, RegisterError> {
+        Ok(RegisterData {
+            address: R::address(),
+            resolution,
+            data: Some(R::encode(value, resolution)?),
+        })
+    }
+}
+
 impl<R> From<Read<R>> for RegisterData
 where
     R: Register + Readable,
@@ -283,6 +422,56 @@ where
     }
 }
 
+/// A table of register addresses that are not part of [`RegisterAddr`].
+///
+/// Custom firmware may expose extra registers. Replies containing an address in this table are parsed
+/// into [`CustomRegisterData`] instead of failing with [`RegisterError::InvalidAddress`].
+///
+/// ```rust
+/// # use moteus::registers::CustomRegisters;
+/// let mut custom = CustomRegisters::new();
+/// custom.add(0x200, "MyRegister").add(0x201, "MyOtherRegister");
+/// assert!(custom.contains(0x200));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomRegisters {
+    registers: HashMap<u16, &'static str>,
+}
+
+impl CustomRegisters {
+    /// Creates an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an address to the table. The name is only used for debugging/display.
+    pub fn add(&mut self, address: u16, name: &'static str) -> &mut Self {
+        let _ = self.registers.insert(address, name);
+        self
+    }
+
+    /// Returns `true` if the address is in the table
+    pub fn contains(&self, address: u16) -> bool {
+        self.registers.contains_key(&address)
+    }
+
+    /// Returns the name given to the address, if it is in the table
+    pub fn name(&self, address: u16) -> Option<&'static str> {
+        self.registers.get(&address).copied()
+    }
+}
+
+/// The raw data returned for a register listed in [`CustomRegisters`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomRegisterData {
+    /// The address of the register
+    pub address: u16,
+    /// The [`Resolution`] of the data
+    pub resolution: Resolution,
+    /// The data returned from the register
+    pub data: Vec<u8>,
+}
+
 /// A sequence of one or more uint8 values, in least significant byte first order.
 /// For each value, the 7 LSBs contain data and if the MSB is set, it means there are more bytes remaining.
 /// At most, it may represent a single uint32 and thus 5 bytes is the maximum valid length.
@@ -291,19 +480,64 @@ pub type Varuint = Vec<u8>;
 impl RegisterAddr {
     /// Converts the address to a [`Varuint`]
     pub fn address_as_bytes(&self) -> Varuint {
-        let mut buf = Vec::new();
-        let mut val = *self as u16;
-        loop {
-            let mut this_byte: u8 = (val & 0x7F) as u8;
-            val >>= 7;
-            this_byte |= if val != 0 { 0x80 } else { 0x00 };
-            buf.push(this_byte);
-
-            if val == 0 {
-                break;
-            }
+        varuint(*self as u16)
+    }
+}
+
+/// Encodes an address as a [`Varuint`]
+pub(crate) fn varuint(mut val: u16) -> Varuint {
+    let mut buf = Vec::new();
+    loop {
+        let mut this_byte: u8 = (val & 0x7F) as u8;
+        val >>= 7;
+        this_byte |= if val != 0 { 0x80 } else { 0x00 };
+        buf.push(this_byte);
+
+        if val == 0 {
+            break;
         }
-        buf
+    }
+    buf
+}
+
+/// Encodes `data` at the resolution `r` with the `mapping` of the register `name`.
+///
+/// If the value overflows `r`, the smallest larger resolution which can hold it is reported
+/// with [`RegisterError::ResolutionTooSmall`].
+fn encode_value<T: TryIntoBytes + Copy>(
+    data: T,
+    r: Resolution,
+    mapping: Map,
+    name: &'static str,
+) -> Result<Vec<u8>, RegisterError> {
+    let encode = |r: Resolution| match r {
+        Resolution::Int8 => data.try_into_1_byte(mapping.0).map(|x| vec![x]),
+        Resolution::Int16 => data.try_into_2_bytes(mapping.1).map(|x| x.to_vec()),
+        Resolution::Int32 => data.try_into_4_bytes(mapping.2).map(|x| x.to_vec()),
+        Resolution::Float => data.try_into_f32_bytes().map(|x| x.to_vec()),
+    };
+    match encode(r) {
+        Err(RegisterError::Overflow) => {
+            let larger = [
+                Resolution::Int8,
+                Resolution::Int16,
+                Resolution::Int32,
+                Resolution::Float,
+            ]
+            .into_iter()
+            .skip_while(|larger| *larger != r)
+            .skip(1)
+            .find(|larger| encode(*larger).is_ok());
+            Err(match larger {
+                Some(required) => RegisterError::ResolutionTooSmall {
+                    register: name,
+                    resolution: r,
+                    required,
+                },
+                None => RegisterError::Overflow,
+            })
+        }
+        result => result,
     }
 }
 
@@ -329,19 +563,33 @@ trait TryFromBytes {
         Self: Sized;
 }
 
-pub(crate) type Map = (f32, f32, f32);
-
-pub(crate) const NO_MAP: Map = (1.0, 1.0, 1.0);
-pub(crate) const POSITION_MAP: Map = (0.01, 0.0001, 0.00001);
-pub(crate) const VELOCITY_MAP: Map = (0.1, 0.00025, 0.00001);
-pub(crate) const ACCEL_MAP: Map = (0.1, 0.00025, 0.00001);
-pub(crate) const TORQUE_MAP: Map = (0.5, 0.01, 0.001);
-pub(crate) const PWM_MAP: Map = (1.0 / 127.0, 1.0 / 32767.0, 1.0 / 2147483647.0);
-pub(crate) const VOLTAGE_MAP: Map = (0.5, 0.1, 0.001);
-pub(crate) const TEMPERATURE_MAP: Map = (1.0, 0.1, 0.001);
-#[allow(unused)]
-pub(crate) const TIME_MAP: Map = (0.01, 0.001, 0.000001);
-pub(crate) const CURRENT_MAP: Map = (1.0, 0.1, 0.001);
+/// The scale of one least significant bit when a register is sent as `(Int8, Int16, Int32)`.
+/// Float values are sent unscaled.
+///
+/// A raw integer is converted to its value by multiplying it by the scale for its resolution.
+/// The mapping of each register is returned by [`RegisterAddr::mapping`].
+pub type Map = (f32, f32, f32);
+
+/// The mapping of registers that are not scaled, such as integer registers
+pub const NO_MAP: Map = (1.0, 1.0, 1.0);
+/// The mapping of positions, in revolutions
+pub const POSITION_MAP: Map = (0.01, 0.0001, 0.00001);
+/// The mapping of velocities, in revolutions per second
+pub const VELOCITY_MAP: Map = (0.1, 0.00025, 0.00001);
+/// The mapping of accelerations, in revolutions per second squared
+pub const ACCEL_MAP: Map = (0.1, 0.00025, 0.00001);
+/// The mapping of torques, in Nm
+pub const TORQUE_MAP: Map = (0.5, 0.01, 0.001);
+/// The mapping of PWM duty cycles, from -1 to 1
+pub const PWM_MAP: Map = (1.0 / 127.0, 1.0 / 32767.0, 1.0 / 2147483647.0);
+/// The mapping of voltages, in volts
+pub const VOLTAGE_MAP: Map = (0.5, 0.1, 0.001);
+/// The mapping of temperatures, in °C
+pub const TEMPERATURE_MAP: Map = (1.0, 0.1, 0.001);
+/// The mapping of times, in seconds
+pub const TIME_MAP: Map = (0.01, 0.001, 0.000001);
+/// The mapping of currents, in amps
+pub const CURRENT_MAP: Map = (1.0, 0.1, 0.001);
 
 /// [`FrameRegisters`] are used to specify the type of data that is being written to or read from a register.
 /// Some, like [`FrameRegisters::ReplyInt8`] and [`FrameRegisters::WriteError`], are only returned in responses.
@@ -349,7 +597,8 @@ pub(crate) const CURRENT_MAP: Map = (1.0, 0.1, 0.001);
 ///
 /// The number of values can be encoded into the 2 Least Significant bits of the [`FrameRegisters`]
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, AsBytes, Hash)]
+#[derive(Debug, Clone, Copy, FromPrimitive, PartialEq, Eq, PartialOrd, Ord, AsBytes, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum FrameRegisters {
     WriteInt8 = 0x00,
@@ -403,7 +652,8 @@ impl FrameRegisters {
 }
 
 /// Each register of the moteus board has an address which can be encoded as a [`Varuint`]
-#[derive(Debug, Clone, Copy, AsBytes, FromPrimitive, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, AsBytes, FromPrimitive, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 #[repr(u16)]
 pub enum RegisterAddr {
@@ -505,13 +755,25 @@ pub enum RegisterAddr {
     MillisecondCounter = 0x070,
     ClockTrim = 0x071,
 
+    Aux1pwm1 = 0x076,
+    Aux1pwm2 = 0x077,
+    Aux1pwm3 = 0x078,
+    Aux1pwm4 = 0x079,
+    Aux1pwm5 = 0x07a,
+
+    Aux2pwm1 = 0x07b,
+    Aux2pwm2 = 0x07c,
+    Aux2pwm3 = 0x07d,
+    Aux2pwm4 = 0x07e,
+    Aux2pwm5 = 0x07f,
+
     RegisterMapVersion = 0x102,
     SerialNumber = 0x120,
     // SerialNumber1 = 0x120,
     // SerialNumber2 = 0x121,
     // SerialNumber3 = 0x122,
+    /// Also known as SetOutputNearest, see [`SetOutputNearest`]
     Rezero = 0x130,
-    // SetOutputNearest = 0x130,
     SetOutputExact = 0x131,
     RequireReindex = 0x132,
 
@@ -519,20 +781,206 @@ pub enum RegisterAddr {
     DriverFault2 = 0x141,
 }
 
+/// The [`RegisterMapVersion`] whose addresses match [`RegisterAddr`].
+///
+/// Controllers reporting another version may place registers at different addresses,
+/// see [`crate::Controller::register_map_version`].
+pub const REGISTER_MAP_VERSION: u32 = 5;
+
+/// Used to generate the runtime lookups from a [`RegisterAddr`] to its register struct
+macro_rules! register_table {
+    ($($(#[$meta:meta])* $addr:ident => $reg:ident),* $(,)?) => {
+        #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+        impl RegisterAddr {
+            /// Every [`RegisterAddr`]
+            pub(crate) const ALL: &'static [RegisterAddr] = &[$($(#[$meta])* RegisterAddr::$addr),*];
+
+            /// Returns the name of the register, as in [`Register::NAME`].
+            ///
+            /// This is the name of the register type, which can differ from the name of the address,
+            /// such as `"FixedVoltage"` for [`RegisterAddr::FixedVoltageOverride`].
+            ///
+            /// ```rust
+            /// # use moteus::registers::RegisterAddr;
+            /// assert_eq!(RegisterAddr::CommandVelocity.name(), "CommandVelocity");
+            /// ```
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => $reg::NAME,)*
+                }
+            }
+
+            /// Returns `true` if the register is a [`CommandRegister`], and `false` for a [`StatusRegister`]
+            pub fn is_writeable(&self) -> bool {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => <$reg as Access>::WRITEABLE,)*
+                }
+            }
+
+            /// Returns the mapping of the register, as in [`Register::MAPPING`].
+            /// Used to scale raw integer values the same way as this crate.
+            pub fn mapping(&self) -> Map {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => $reg::MAPPING,)*
+                }
+            }
+
+            /// Returns the default resolution of the register, as in [`Register::DEFAULT_RESOLUTION`]
+            pub(crate) fn default_resolution(&self) -> Resolution {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => $reg::DEFAULT_RESOLUTION,)*
+                }
+            }
+
+            /// Encodes `value` into the register at the given resolution, applying the register's mapping
+            pub(crate) fn write_f64(
+                &self,
+                value: f64,
+                resolution: Resolution,
+            ) -> Result<RegisterData, RegisterError> {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => write_f64::<$reg>(value, resolution),)*
+                }
+            }
+
+            /// Decodes the data of a [`RegisterData`] at this address into its natural type
+            pub(crate) fn read_value(&self, data: &RegisterData) -> Result<RegisterValue, RegisterError> {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => read_value::<$reg>(data),)*
+                }
+            }
+
+            /// Decodes the data of a [`RegisterData`] at this address into an `f64`
+            pub(crate) fn read_f64(&self, data: &RegisterData) -> Result<f64, RegisterError> {
+                match self {
+                    $($(#[$meta])* RegisterAddr::$addr => read_f64::<$reg>(data),)*
+                }
+            }
+        }
+    };
+}
+
+/// A decoded register value, used when the register type is only known at runtime.
+///
+/// See [`crate::ResponseFrame::to_map`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegisterValue {
+    /// A register without a value, such as [`RequireReindex`]
+    None,
+    /// A float register, with the register's mapping applied
+    Float(f32),
+    /// An `i8` register, such as [`TrajectoryComplete`]
+    Int8(i8),
+    /// An `i32` register, such as [`MillisecondCounter`]
+    Int32(i32),
+    /// A `u32` register, such as [`SerialNumber`]
+    UInt32(u32),
+    /// The [`Mode`] register
+    Mode(Modes),
+    /// The [`Fault`] register
+    Fault(Faults),
+    /// The [`HomeState`] register
+    HomeState(HomeStates),
+}
+
+impl RegisterValue {
+    /// Returns the value as an `f64`, enums are converted to their discriminant
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            RegisterValue::None => 0.0,
+            RegisterValue::Float(v) => v.to_f64(),
+            RegisterValue::Int8(v) => v.to_f64(),
+            RegisterValue::Int32(v) => v.to_f64(),
+            RegisterValue::UInt32(v) => v.to_f64(),
+            RegisterValue::Mode(v) => v.to_f64(),
+            RegisterValue::Fault(v) => v.to_f64(),
+            RegisterValue::HomeState(v) => v.to_f64(),
+        }
+    }
+}
+
+/// Register values which can be represented as an `f64`, used when the register type is only known at runtime
+trait NumericValue: Sized {
+    /// Whether negative values are out of range, rather than invalid for another reason
+    const UNSIGNED: bool = false;
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    fn from_f64(value: f64) -> Option<Self>;
+    fn to_f64(&self) -> f64;
+    fn to_value(self) -> RegisterValue;
+}
+
+fn read_value<R>(data: &RegisterData) -> Result<RegisterValue, RegisterError>
+where
+    R: Register,
+    R::INNER: NumericValue,
+{
+    Ok(data.as_res::<R>()?.value.to_value())
+}
+
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+fn write_f64<R>(value: f64, resolution: Resolution) -> Result<RegisterData, RegisterError>
+where
+    R: Encode,
+    R::INNER: NumericValue,
+{
+    if R::INNER::UNSIGNED && value < 0.0 {
+        return Err(RegisterError::NegativeValue);
+    }
+    let inner = R::INNER::from_f64(value).ok_or(RegisterError::InvalidData)?;
+    RegisterData::encode::<R>(inner, resolution)
+}
+
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+fn read_f64<R>(data: &RegisterData) -> Result<f64, RegisterError>
+where
+    R: Register,
+    R::INNER: NumericValue,
+{
+    Ok(data.as_res::<R>()?.value.to_f64())
+}
+
+impl RegisterAddr {
+    /// Returns the register written to reproduce the value of this register on a controller.
+    ///
+    /// Writable registers are their own command register. The measured [`RegisterAddr::Position`] and
+    /// [`RegisterAddr::Velocity`] are commanded with [`RegisterAddr::CommandPosition`] and
+    /// [`RegisterAddr::CommandVelocity`], which share their mapping. Other status registers have no
+    /// command register, and [`None`] is returned.
+    pub fn command_register(&self) -> Option<RegisterAddr> {
+        match self {
+            RegisterAddr::Position => Some(RegisterAddr::CommandPosition),
+            RegisterAddr::Velocity => Some(RegisterAddr::CommandVelocity),
+            addr if addr.is_writeable() => Some(*addr),
+            _ => None,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+impl RegisterAddr {
+    /// Returns the address of the register with the given name, as in [`Register::NAME`]
+    pub(crate) fn from_name(name: &str) -> Option<RegisterAddr> {
+        RegisterAddr::ALL
+            .iter()
+            .find(|addr| addr.name() == name)
+            .copied()
+    }
+}
+
 int_rw_register!(Mode: RegisterAddr::Mode, Modes, Resolution::Int8);
-map_rw_register!(Position: RegisterAddr::Position, POSITION_MAP);
-map_rw_register!(Velocity: RegisterAddr::Velocity, VELOCITY_MAP);
-map_rw_register!(Torque: RegisterAddr::Torque, TORQUE_MAP);
-map_rw_register!(QCurrent: RegisterAddr::QCurrent, CURRENT_MAP);
-map_rw_register!(DCurrent: RegisterAddr::DCurrent, CURRENT_MAP);
-map_rw_register!(AbsPosition: RegisterAddr::AbsPosition, POSITION_MAP);
-
-map_rw_register!(MotorTemperature: RegisterAddr::MotorTemperature, TEMPERATURE_MAP);
-int_rw_register!(TrajectoryComplete: RegisterAddr::TrajectoryComplete, i8, Resolution::Int8);
-int_rw_register!(HomeState: RegisterAddr::HomeState, HomeStates, Resolution::Int8);
-map_rw_register!(Voltage: RegisterAddr::Voltage, VOLTAGE_MAP);
-map_rw_register!(Temperature: RegisterAddr::Temperature, TEMPERATURE_MAP);
-int_rw_register!(Fault: RegisterAddr::Fault, Faults, Resolution::Int8);
+map_ro_register!(Position: RegisterAddr::Position, POSITION_MAP);
+map_ro_register!(Velocity: RegisterAddr::Velocity, VELOCITY_MAP);
+map_ro_register!(Torque: RegisterAddr::Torque, TORQUE_MAP);
+map_ro_register!(QCurrent: RegisterAddr::QCurrent, CURRENT_MAP);
+map_ro_register!(DCurrent: RegisterAddr::DCurrent, CURRENT_MAP);
+map_ro_register!(AbsPosition: RegisterAddr::AbsPosition, POSITION_MAP);
+
+map_ro_register!(MotorTemperature: RegisterAddr::MotorTemperature, TEMPERATURE_MAP);
+int_ro_register!(TrajectoryComplete: RegisterAddr::TrajectoryComplete, i8, Resolution::Int8);
+int_ro_register!(HomeState: RegisterAddr::HomeState, HomeStates, Resolution::Int8);
+map_ro_register!(Voltage: RegisterAddr::Voltage, VOLTAGE_MAP);
+map_ro_register!(Temperature: RegisterAddr::Temperature, TEMPERATURE_MAP);
+int_ro_register!(Fault: RegisterAddr::Fault, Faults, Resolution::Int8);
 
 map_rw_register!(PwmPhaseA: RegisterAddr::PwmPhaseA, PWM_MAP);
 map_rw_register!(PwmPhaseB: RegisterAddr::PwmPhaseB, PWM_MAP);
@@ -544,8 +992,8 @@ map_rw_register!(VoltagePhaseC: RegisterAddr::VoltagePhaseC, VOLTAGE_MAP);
 
 map_rw_register!(VfocTheta: RegisterAddr::VfocTheta, NO_MAP);
 map_rw_register!(VfocVoltage: RegisterAddr::VfocVoltage, NO_MAP);
-map_rw_register!(VoltageDqD: RegisterAddr::VoltageDqD, NO_MAP);
-map_rw_register!(VoltageDqQ: RegisterAddr::VoltageDqQ, NO_MAP);
+map_rw_register!(VoltageDqD: RegisterAddr::VoltageDqD, VOLTAGE_MAP);
+map_rw_register!(VoltageDqQ: RegisterAddr::VoltageDqQ, VOLTAGE_MAP);
 
 map_rw_register!(CommandQcurrent: RegisterAddr::CommandQCurrent, CURRENT_MAP);
 map_rw_register!(CommandDcurrent: RegisterAddr::CommandDCurrent, CURRENT_MAP);
@@ -557,23 +1005,23 @@ map_rw_register!(CommandKpScale: RegisterAddr::CommandKpScale, TORQUE_MAP);
 map_rw_register!(CommandKdScale: RegisterAddr::CommandKdScale, TORQUE_MAP);
 map_rw_register!(CommandPositionMaxTorque: RegisterAddr::CommandPositionMaxTorque, TORQUE_MAP);
 map_rw_register!(CommandStopPosition: RegisterAddr::CommandStopPosition, POSITION_MAP);
-map_rw_register!(CommandTimeout: RegisterAddr::CommandTimeout, NO_MAP);
+map_rw_register!(CommandTimeout: RegisterAddr::CommandTimeout, TIME_MAP);
 map_rw_register!(VelocityLimit: RegisterAddr::VelocityLimit, VELOCITY_MAP);
 map_rw_register!(AccelerationLimit: RegisterAddr::AccelerationLimit, ACCEL_MAP);
 map_rw_register!(FixedVoltage: RegisterAddr::FixedVoltageOverride, VOLTAGE_MAP);
 
-map_rw_register!(PositionKp: RegisterAddr::PositionKp, TORQUE_MAP);
-map_rw_register!(PositionKi: RegisterAddr::PositionKi, TORQUE_MAP);
-map_rw_register!(PositionKd: RegisterAddr::PositionKd, TORQUE_MAP);
-map_rw_register!(PositionFeedforward: RegisterAddr::PositionFeedforward, TORQUE_MAP);
-map_rw_register!(PositionCommand: RegisterAddr::PositionCommand, TORQUE_MAP);
+map_ro_register!(PositionKp: RegisterAddr::PositionKp, TORQUE_MAP);
+map_ro_register!(PositionKi: RegisterAddr::PositionKi, TORQUE_MAP);
+map_ro_register!(PositionKd: RegisterAddr::PositionKd, TORQUE_MAP);
+map_ro_register!(PositionFeedforward: RegisterAddr::PositionFeedforward, TORQUE_MAP);
+map_ro_register!(PositionCommand: RegisterAddr::PositionCommand, TORQUE_MAP);
 
-map_rw_register!(ControlPosition: RegisterAddr::ControlPosition, POSITION_MAP);
-map_rw_register!(ControlVelocity: RegisterAddr::ControlVelocity, VELOCITY_MAP);
-map_rw_register!(ControlTorque: RegisterAddr::ControlTorque, TORQUE_MAP);
-map_rw_register!(ControlPositionError: RegisterAddr::ControlPositionError, POSITION_MAP);
-map_rw_register!(ControlVelocityError: RegisterAddr::ControlVelocityError, VELOCITY_MAP);
-map_rw_register!(ControlTorqueError: RegisterAddr::ControlTorqueError, TORQUE_MAP);
+map_ro_register!(ControlPosition: RegisterAddr::ControlPosition, POSITION_MAP);
+map_ro_register!(ControlVelocity: RegisterAddr::ControlVelocity, VELOCITY_MAP);
+map_ro_register!(ControlTorque: RegisterAddr::ControlTorque, TORQUE_MAP);
+map_ro_register!(ControlPositionError: RegisterAddr::ControlPositionError, POSITION_MAP);
+map_ro_register!(ControlVelocityError: RegisterAddr::ControlVelocityError, VELOCITY_MAP);
+map_ro_register!(ControlTorqueError: RegisterAddr::ControlTorqueError, TORQUE_MAP);
 
 map_rw_register!(CommandStayWithinLowerBound: RegisterAddr::CommandStayWithinLowerBound, NO_MAP); //TODO: check the mapping
 map_rw_register!(CommandStayWithinUpperBound: RegisterAddr::CommandStayWithinUpperBound, NO_MAP);
@@ -583,48 +1031,222 @@ map_rw_register!(CommandStayWithinKdScale: RegisterAddr::CommandStayWithinKdScal
 map_rw_register!(CommandStayWithinPositionMaxTorque: RegisterAddr::CommandStayWithinPositionMaxTorque, NO_MAP);
 map_rw_register!(CommandStayWithinTimeout: RegisterAddr::CommandStayWithinTimeout, NO_MAP);
 
-map_rw_register!(Encoder0position: RegisterAddr::Encoder0position, POSITION_MAP);
-map_rw_register!(Encoder0velocity: RegisterAddr::Encoder0velocity, VELOCITY_MAP);
-map_rw_register!(Encoder1position: RegisterAddr::Encoder1position, POSITION_MAP);
-map_rw_register!(Encoder1velocity: RegisterAddr::Encoder1velocity, VELOCITY_MAP);
-map_rw_register!(Encoder2position: RegisterAddr::Encoder2position, POSITION_MAP);
-map_rw_register!(Encoder2velocity: RegisterAddr::Encoder2velocity, VELOCITY_MAP);
+map_ro_register!(Encoder0position: RegisterAddr::Encoder0position, POSITION_MAP);
+map_ro_register!(Encoder0velocity: RegisterAddr::Encoder0velocity, VELOCITY_MAP);
+map_ro_register!(Encoder1position: RegisterAddr::Encoder1position, POSITION_MAP);
+map_ro_register!(Encoder1velocity: RegisterAddr::Encoder1velocity, VELOCITY_MAP);
+map_ro_register!(Encoder2position: RegisterAddr::Encoder2position, POSITION_MAP);
+map_ro_register!(Encoder2velocity: RegisterAddr::Encoder2velocity, VELOCITY_MAP);
 
-int_rw_register!(EncoderValidity: RegisterAddr::EncoderValidity, i8, Resolution::Int8);
+int_ro_register!(EncoderValidity: RegisterAddr::EncoderValidity, i8, Resolution::Int8);
 
 #[cfg(feature = "aux_index_raw")]
-int_rw_register!(Aux1IndexRaw: RegisterAddr::Aux1IndexRaw, i8, Resolution::Int8);
+int_ro_register!(Aux1IndexRaw: RegisterAddr::Aux1IndexRaw, i8, Resolution::Int8);
 #[cfg(feature = "aux_index_raw")]
-int_rw_register!(Aux2IndexRaw: RegisterAddr::Aux2IndexRaw, i8, Resolution::Int8);
+int_ro_register!(Aux2IndexRaw: RegisterAddr::Aux2IndexRaw, i8, Resolution::Int8);
 
 int_rw_register!(Aux1gpioCommand: RegisterAddr::Aux1gpioCommand, i8, Resolution::Int8);
 int_rw_register!(Aux2gpioCommand: RegisterAddr::Aux2gpioCommand, i8, Resolution::Int8);
-int_rw_register!(Aux1gpioStatus: RegisterAddr::Aux1gpioStatus, i8, Resolution::Int8);
-int_rw_register!(Aux2gpioStatus: RegisterAddr::Aux2gpioStatus, i8, Resolution::Int8);
-
-map_rw_register!(Aux1analogIn1: RegisterAddr::Aux1analogIn1, PWM_MAP);
-map_rw_register!(Aux1analogIn2: RegisterAddr::Aux1analogIn2, PWM_MAP);
-map_rw_register!(Aux1analogIn3: RegisterAddr::Aux1analogIn3, PWM_MAP);
-map_rw_register!(Aux1analogIn4: RegisterAddr::Aux1analogIn4, PWM_MAP);
-map_rw_register!(Aux1analogIn5: RegisterAddr::Aux1analogIn5, PWM_MAP);
-
-map_rw_register!(Aux2analogIn1: RegisterAddr::Aux2analogIn1, PWM_MAP);
-map_rw_register!(Aux2analogIn2: RegisterAddr::Aux2analogIn2, PWM_MAP);
-map_rw_register!(Aux2analogIn3: RegisterAddr::Aux2analogIn3, PWM_MAP);
-map_rw_register!(Aux2analogIn4: RegisterAddr::Aux2analogIn4, PWM_MAP);
-map_rw_register!(Aux2analogIn5: RegisterAddr::Aux2analogIn5, PWM_MAP);
-
-int_rw_register!(MillisecondCounter: RegisterAddr::MillisecondCounter, i32, Resolution::Int32);
-int_rw_register!(ClockTrim: RegisterAddr::ClockTrim, i32, Resolution::Int8);
-
-int_rw_register!(RegisterMapVersion: RegisterAddr::RegisterMapVersion, u32, Resolution::Int32);
-int_rw_register!(SerialNumber: RegisterAddr::SerialNumber, u32, Resolution::Int32);
-int_rw_register!(Rezero: RegisterAddr::Rezero, i8, Resolution::Int8);
-int_rw_register!(SetOutputExact: RegisterAddr::SetOutputExact, i8, Resolution::Int8);
+int_ro_register!(Aux1gpioStatus: RegisterAddr::Aux1gpioStatus, i8, Resolution::Int8);
+int_ro_register!(Aux2gpioStatus: RegisterAddr::Aux2gpioStatus, i8, Resolution::Int8);
+
+map_ro_register!(Aux1analogIn1: RegisterAddr::Aux1analogIn1, PWM_MAP);
+map_ro_register!(Aux1analogIn2: RegisterAddr::Aux1analogIn2, PWM_MAP);
+map_ro_register!(Aux1analogIn3: RegisterAddr::Aux1analogIn3, PWM_MAP);
+map_ro_register!(Aux1analogIn4: RegisterAddr::Aux1analogIn4, PWM_MAP);
+map_ro_register!(Aux1analogIn5: RegisterAddr::Aux1analogIn5, PWM_MAP);
+
+map_ro_register!(Aux2analogIn1: RegisterAddr::Aux2analogIn1, PWM_MAP);
+map_ro_register!(Aux2analogIn2: RegisterAddr::Aux2analogIn2, PWM_MAP);
+map_ro_register!(Aux2analogIn3: RegisterAddr::Aux2analogIn3, PWM_MAP);
+map_ro_register!(Aux2analogIn4: RegisterAddr::Aux2analogIn4, PWM_MAP);
+map_ro_register!(Aux2analogIn5: RegisterAddr::Aux2analogIn5, PWM_MAP);
+
+int_ro_register!(MillisecondCounter: RegisterAddr::MillisecondCounter, i32, Resolution::Int32);
+int_rw_register!(ClockTrim: RegisterAddr::ClockTrim, i8, Resolution::Int8);
+
+map_rw_register!(Aux1pwm1: RegisterAddr::Aux1pwm1, PWM_MAP);
+map_rw_register!(Aux1pwm2: RegisterAddr::Aux1pwm2, PWM_MAP);
+map_rw_register!(Aux1pwm3: RegisterAddr::Aux1pwm3, PWM_MAP);
+map_rw_register!(Aux1pwm4: RegisterAddr::Aux1pwm4, PWM_MAP);
+map_rw_register!(Aux1pwm5: RegisterAddr::Aux1pwm5, PWM_MAP);
+
+map_rw_register!(Aux2pwm1: RegisterAddr::Aux2pwm1, PWM_MAP);
+map_rw_register!(Aux2pwm2: RegisterAddr::Aux2pwm2, PWM_MAP);
+map_rw_register!(Aux2pwm3: RegisterAddr::Aux2pwm3, PWM_MAP);
+map_rw_register!(Aux2pwm4: RegisterAddr::Aux2pwm4, PWM_MAP);
+map_rw_register!(Aux2pwm5: RegisterAddr::Aux2pwm5, PWM_MAP);
+
+int_ro_register!(RegisterMapVersion: RegisterAddr::RegisterMapVersion, u32, Resolution::Int32);
+int_ro_register!(SerialNumber: RegisterAddr::SerialNumber, u32, Resolution::Int32);
+map_rw_register!(Rezero: RegisterAddr::Rezero, POSITION_MAP);
+map_rw_register!(SetOutputExact: RegisterAddr::SetOutputExact, POSITION_MAP);
+
+/// Sets the output position to the position nearest the current one which is equivalent to the written
+/// position, modulo one revolution of the output. It shares the address of [`Rezero`].
+///
+/// Use this to zero a wrapped output, such as a wheel or a joint which can turn freely, where a whole
+/// revolution lands on the same physical position. Use [`SetOutputExact`] to set the position to exactly
+/// the written value, such as for a geared output where the revolutions of the output are meaningful.
+pub type SetOutputNearest = Rezero;
 int_rw_register!(RequireReindex: RegisterAddr::RequireReindex, (), Resolution::Int8);
 
-int_rw_register!(DriverFault1: RegisterAddr::DriverFault1, u32, Resolution::Int32);
-int_rw_register!(DriverFault2: RegisterAddr::DriverFault2, u32, Resolution::Int32);
+int_ro_register!(DriverFault1: RegisterAddr::DriverFault1, u32, Resolution::Int32);
+int_ro_register!(DriverFault2: RegisterAddr::DriverFault2, u32, Resolution::Int32);
+
+impl CommandPosition {
+    /// Writes a position given in radians, converted to the revolutions used by the moteus.
+    pub fn from_radians(radians: f32) -> Result<Write<Self>, RegisterError> {
+        Self::write(radians / TAU)
+    }
+}
+
+impl CommandStopPosition {
+    /// Writes a stop position given in radians, converted to the revolutions used by the moteus.
+    pub fn from_radians(radians: f32) -> Result<Write<Self>, RegisterError> {
+        Self::write(radians / TAU)
+    }
+}
+
+impl CommandVelocity {
+    /// Writes a velocity given in radians per second, converted to the revolutions per second used by the moteus.
+    pub fn from_rad_per_sec(rad_per_sec: f32) -> Result<Write<Self>, RegisterError> {
+        Self::write(rad_per_sec / TAU)
+    }
+}
+
+impl CommandTimeout {
+    /// Writes a watchdog timeout given as a [`Duration`], converted to the seconds used by the moteus.
+    ///
+    /// A timeout of zero uses `servo.default_timeout_s`, and writing `f32::NAN` with [`Writeable::write`]
+    /// disables the watchdog for the command.
+    ///
+    /// ```rust
+    /// # use moteus::registers::CommandTimeout;
+    /// # use std::time::Duration;
+    /// let timeout = CommandTimeout::from_duration(Duration::from_millis(200))?;
+    /// # Ok::<(), moteus::RegisterError>(())
+    /// ```
+    pub fn from_duration(timeout: Duration) -> Result<Write<Self>, RegisterError> {
+        Self::write(timeout.as_secs_f32())
+    }
+}
+
+impl Res<CommandTimeout> {
+    /// Returns the watchdog timeout as a [`Duration`], or [`None`] if it is NaN, which disables the watchdog.
+    /// A timeout of zero means `servo.default_timeout_s` is used.
+    pub fn as_duration(&self) -> Option<Duration> {
+        Duration::try_from_secs_f32(self.value).ok()
+    }
+}
+
+impl VelocityLimit {
+    /// Writes a velocity limit given in radians per second, converted to the revolutions per second used by the moteus.
+    pub fn from_rad_per_sec(rad_per_sec: f32) -> Result<Write<Self>, RegisterError> {
+        Self::write(rad_per_sec / TAU)
+    }
+}
+
+register_table! {
+    Mode => Mode,
+    Position => Position,
+    Velocity => Velocity,
+    Torque => Torque,
+    QCurrent => QCurrent,
+    DCurrent => DCurrent,
+    AbsPosition => AbsPosition,
+    MotorTemperature => MotorTemperature,
+    TrajectoryComplete => TrajectoryComplete,
+    HomeState => HomeState,
+    Voltage => Voltage,
+    Temperature => Temperature,
+    Fault => Fault,
+    PwmPhaseA => PwmPhaseA,
+    PwmPhaseB => PwmPhaseB,
+    PwmPhaseC => PwmPhaseC,
+    VoltagePhaseA => VoltagePhaseA,
+    VoltagePhaseB => VoltagePhaseB,
+    VoltagePhaseC => VoltagePhaseC,
+    VfocTheta => VfocTheta,
+    VfocVoltage => VfocVoltage,
+    VoltageDqD => VoltageDqD,
+    VoltageDqQ => VoltageDqQ,
+    CommandQCurrent => CommandQcurrent,
+    CommandDCurrent => CommandDcurrent,
+    CommandPosition => CommandPosition,
+    CommandVelocity => CommandVelocity,
+    CommandFeedforwardTorque => CommandFeedforwardTorque,
+    CommandKpScale => CommandKpScale,
+    CommandKdScale => CommandKdScale,
+    CommandPositionMaxTorque => CommandPositionMaxTorque,
+    CommandStopPosition => CommandStopPosition,
+    CommandTimeout => CommandTimeout,
+    VelocityLimit => VelocityLimit,
+    AccelerationLimit => AccelerationLimit,
+    FixedVoltageOverride => FixedVoltage,
+    PositionKp => PositionKp,
+    PositionKi => PositionKi,
+    PositionKd => PositionKd,
+    PositionFeedforward => PositionFeedforward,
+    PositionCommand => PositionCommand,
+    ControlPosition => ControlPosition,
+    ControlVelocity => ControlVelocity,
+    ControlTorque => ControlTorque,
+    ControlPositionError => ControlPositionError,
+    ControlVelocityError => ControlVelocityError,
+    ControlTorqueError => ControlTorqueError,
+    CommandStayWithinLowerBound => CommandStayWithinLowerBound,
+    CommandStayWithinUpperBound => CommandStayWithinUpperBound,
+    CommandStayWithinFeedforwardTorque => CommandStayWithinFeedforwardTorque,
+    CommandStayWithinKpScale => CommandStayWithinKpScale,
+    CommandStayWithinKdScale => CommandStayWithinKdScale,
+    CommandStayWithinPositionMaxTorque => CommandStayWithinPositionMaxTorque,
+    CommandStayWithinTimeout => CommandStayWithinTimeout,
+    Encoder0position => Encoder0position,
+    Encoder0velocity => Encoder0velocity,
+    Encoder1position => Encoder1position,
+    Encoder1velocity => Encoder1velocity,
+    Encoder2position => Encoder2position,
+    Encoder2velocity => Encoder2velocity,
+    EncoderValidity => EncoderValidity,
+    #[cfg(feature = "aux_index_raw")]
+    Aux1IndexRaw => Aux1IndexRaw,
+    #[cfg(feature = "aux_index_raw")]
+    Aux2IndexRaw => Aux2IndexRaw,
+    Aux1gpioCommand => Aux1gpioCommand,
+    Aux2gpioCommand => Aux2gpioCommand,
+    Aux1gpioStatus => Aux1gpioStatus,
+    Aux2gpioStatus => Aux2gpioStatus,
+    Aux1analogIn1 => Aux1analogIn1,
+    Aux1analogIn2 => Aux1analogIn2,
+    Aux1analogIn3 => Aux1analogIn3,
+    Aux1analogIn4 => Aux1analogIn4,
+    Aux1analogIn5 => Aux1analogIn5,
+    Aux2analogIn1 => Aux2analogIn1,
+    Aux2analogIn2 => Aux2analogIn2,
+    Aux2analogIn3 => Aux2analogIn3,
+    Aux2analogIn4 => Aux2analogIn4,
+    Aux2analogIn5 => Aux2analogIn5,
+    MillisecondCounter => MillisecondCounter,
+    ClockTrim => ClockTrim,
+    Aux1pwm1 => Aux1pwm1,
+    Aux1pwm2 => Aux1pwm2,
+    Aux1pwm3 => Aux1pwm3,
+    Aux1pwm4 => Aux1pwm4,
+    Aux1pwm5 => Aux1pwm5,
+    Aux2pwm1 => Aux2pwm1,
+    Aux2pwm2 => Aux2pwm2,
+    Aux2pwm3 => Aux2pwm3,
+    Aux2pwm4 => Aux2pwm4,
+    Aux2pwm5 => Aux2pwm5,
+    RegisterMapVersion => RegisterMapVersion,
+    SerialNumber => SerialNumber,
+    Rezero => Rezero,
+    SetOutputExact => SetOutputExact,
+    RequireReindex => RequireReindex,
+    DriverFault1 => DriverFault1,
+    DriverFault2 => DriverFault2,
+}
 
 impl TryIntoBytes for () {
     fn try_into_1_byte(self, _scale: f32) -> Result<u8, RegisterError> {
@@ -737,14 +1359,15 @@ impl TryFromBytes for i32 {
 impl TryIntoBytes for u32 {
     fn try_into_1_byte(self, _scale: f32) -> Result<u8, RegisterError> {
         let value = self;
-        if value > i8::MAX as u32 || value < i8::MIN as u32 {
+        // the moteus reads integer registers as signed, so only the positive half of the range fits
+        if value > i8::MAX as u32 {
             return Err(RegisterError::Overflow);
         }
         Ok(value as u8)
     }
     fn try_into_2_bytes(self, _scale: f32) -> Result<[u8; 2], RegisterError> {
         let value = self;
-        if value > i16::MAX as u32 || value < i16::MIN as u32 {
+        if value > i16::MAX as u32 {
             return Err(RegisterError::Overflow);
         }
         Ok((value as u16).to_le_bytes())
@@ -860,6 +1483,68 @@ impl TryFromBytes for f32 {
     }
 }
 
+impl NumericValue for () {
+    fn from_f64(_value: f64) -> Option<Self> {
+        Some(())
+    }
+    fn to_f64(&self) -> f64 {
+        0.0
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::None
+    }
+}
+
+impl NumericValue for f32 {
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(value as f32)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::Float(self)
+    }
+}
+
+impl NumericValue for i8 {
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::Int8(self)
+    }
+}
+
+impl NumericValue for i32 {
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::Int32(self)
+    }
+}
+
+impl NumericValue for u32 {
+    const UNSIGNED: bool = true;
+
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::UInt32(self)
+    }
+}
+
 impl<R> TryFrom<f32> for Write<R>
 where
     R: Register<INNER = f32> + Writeable,
@@ -893,6 +1578,18 @@ pub enum Modes {
     Brake = 15,
 }
 
+impl NumericValue for Modes {
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as u8 as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::Mode(self)
+    }
+}
+
 impl TryIntoBytes for Modes {
     fn try_into_1_byte(self, _scale: f32) -> Result<u8, RegisterError> {
         Ok(self as u8)
@@ -922,8 +1619,10 @@ impl TryFromBytes for Modes {
         let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
         Modes::from_u32(value).ok_or(RegisterError::InvalidData)
     }
-    fn try_from_f32_bytes(_bytes: &[u8]) -> Result<Self, RegisterError> {
-        Err(RegisterError::IntAsFloat)
+    fn try_from_f32_bytes(bytes: &[u8]) -> Result<Self, RegisterError> {
+        let mut rdr = std::io::Cursor::new(bytes);
+        let value = rdr.read_f32::<LE>()?;
+        Modes::from_f32(value).ok_or(RegisterError::InvalidData)
     }
 }
 
@@ -954,10 +1653,80 @@ pub enum Faults {
     ThetaInvalid = 42,
     PositionInvalid = 43,
     DriverEnableFault = 44,
+    /// The [`CommandStopPosition`] was used together with a [`VelocityLimit`] or [`AccelerationLimit`],
+    /// which current firmware refuses as the stop position is deprecated. See [`Faults::hint`].
     StopPositionDeprecated = 45,
     TimingViolation = 46,
 }
 
+impl Faults {
+    /// Returns advice on resolving the fault, for faults caused by how the controller was commanded
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Faults::StopPositionDeprecated => Some(
+                "the stop position can't be combined with velocity or acceleration limits, \
+                 command the target as the position and let the limits shape the move instead",
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if sending a [`crate::frame::Stop`] clears the fault and the controller can be commanded
+    /// again without attention to the hardware, `false` if it needs intervention before it is commanded again.
+    ///
+    /// [`Faults::Success`] is recoverable as there is nothing to recover from. The classification is:
+    /// - recoverable: the DMA and UART errors, [`Faults::PwmCycleOverrun`] and [`Faults::TimingViolation`] are
+    ///   transient errors inside the controller. [`Faults::OverVoltage`] and [`Faults::UnderVoltage`] are usually
+    ///   excursions of the bus voltage from regenerative braking or a sagging supply, so check the voltage with
+    ///   [`crate::Controller::bus_voltage`] before retrying. [`Faults::ConfigChanged`] only needs the controller to
+    ///   be stopped, and [`Faults::StopPositionDeprecated`] is cleared by fixing the command, see [`Faults::hint`].
+    /// - fatal: [`Faults::MotorDriverFault`] and [`Faults::DriverEnableFault`] point to the gate driver or the
+    ///   power stage, and [`Faults::EncoderFault`] to a failed or disconnected encoder, which may be damaged.
+    ///   [`Faults::CalibrationFault`] and [`Faults::MotorNotConfigured`] need the motor calibrated or configured.
+    ///   [`Faults::ThetaInvalid`] and [`Faults::PositionInvalid`] need the encoders to become valid, such as by
+    ///   homing. [`Faults::OverTemperature`] needs the controller to cool down, and [`Faults::StartOutsideLimit`]
+    ///   needs the output moved back within the position limits, as retrying faults again until then.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Faults::Success
+            | Faults::DmaStreamTransferError
+            | Faults::DmaStreamFifoError
+            | Faults::UartOverrunError
+            | Faults::UartFramingError
+            | Faults::UartNoiseError
+            | Faults::UartBufferOverrunError
+            | Faults::UartParityError
+            | Faults::PwmCycleOverrun
+            | Faults::TimingViolation
+            | Faults::OverVoltage
+            | Faults::UnderVoltage
+            | Faults::ConfigChanged
+            | Faults::StopPositionDeprecated => true,
+            Faults::MotorDriverFault
+            | Faults::DriverEnableFault
+            | Faults::EncoderFault
+            | Faults::CalibrationFault
+            | Faults::MotorNotConfigured
+            | Faults::ThetaInvalid
+            | Faults::PositionInvalid
+            | Faults::OverTemperature
+            | Faults::StartOutsideLimit => false,
+        }
+    }
+}
+
+impl NumericValue for Faults {
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as u8 as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::Fault(self)
+    }
+}
+
 impl TryIntoBytes for Faults {
     fn try_into_1_byte(self, _scale: f32) -> Result<u8, RegisterError> {
         Ok(self as u8)
@@ -987,8 +1756,10 @@ impl TryFromBytes for Faults {
         let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
         Faults::from_u32(value).ok_or(RegisterError::InvalidData)
     }
-    fn try_from_f32_bytes(_bytes: &[u8]) -> Result<Self, RegisterError> {
-        Err(RegisterError::IntAsFloat)
+    fn try_from_f32_bytes(bytes: &[u8]) -> Result<Self, RegisterError> {
+        let mut rdr = std::io::Cursor::new(bytes);
+        let value = rdr.read_f32::<LE>()?;
+        Faults::from_f32(value).ok_or(RegisterError::InvalidData)
     }
 }
 
@@ -1001,6 +1772,18 @@ pub enum HomeStates {
     Output = 2,
 }
 
+impl NumericValue for HomeStates {
+    fn from_f64(value: f64) -> Option<Self> {
+        FromPrimitive::from_f64(value)
+    }
+    fn to_f64(&self) -> f64 {
+        *self as u8 as f64
+    }
+    fn to_value(self) -> RegisterValue {
+        RegisterValue::HomeState(self)
+    }
+}
+
 impl TryIntoBytes for HomeStates {
     fn try_into_1_byte(self, _scale: f32) -> Result<u8, RegisterError> {
         Ok(self as u8)
@@ -1030,8 +1813,10 @@ impl TryFromBytes for HomeStates {
         let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
         HomeStates::from_u32(value).ok_or(RegisterError::InvalidData)
     }
-    fn try_from_f32_bytes(_bytes: &[u8]) -> Result<Self, RegisterError> {
-        Err(RegisterError::IntAsFloat)
+    fn try_from_f32_bytes(bytes: &[u8]) -> Result<Self, RegisterError> {
+        let mut rdr = std::io::Cursor::new(bytes);
+        let value = rdr.read_f32::<LE>()?;
+        HomeStates::from_f32(value).ok_or(RegisterError::InvalidData)
     }
 }
 
@@ -1041,48 +1826,44 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_mapping() {
+        assert_eq!(RegisterAddr::AbsPosition.mapping(), POSITION_MAP);
+        assert_eq!(RegisterAddr::Mode.mapping(), NO_MAP);
+        // the mapping scales raw values the same way as the registers
+        let raw = i16::from_le_bytes([0x64, 0x00]) as f32;
+        let value = Position::from_bytes(&[0x64, 0x00], Resolution::Int16).unwrap();
+        assert_eq!(raw * RegisterAddr::Position.mapping().1, value);
+    }
+
     #[test]
     fn test_f32_register() {
-        let position = Position::write(2.0).unwrap();
-        let data = position.data;
+        let data = Position::encode(2.0, Resolution::Float).unwrap();
         assert_eq!(data, vec![0, 0, 0, 64]);
         let from_data = Position::from_bytes(&data, Resolution::Float).unwrap();
         assert_eq!(from_data, 2.0);
 
-        let data = Position::write_with_resolution(2.0, Resolution::Int8);
+        let data = Position::encode(2.0, Resolution::Int8);
         assert!(data.is_err()); // OVERFLOW
-        let data = Position::write_with_resolution(2.0, Resolution::Int16)
-            .unwrap()
-            .data;
+        let data = Position::encode(2.0, Resolution::Int16).unwrap();
         assert_eq!(data, 20000i16.to_le_bytes().to_vec());
-        let data = Position::write_with_resolution(2.0, Resolution::Int32)
-            .unwrap()
-            .data;
+        let data = Position::encode(2.0, Resolution::Int32).unwrap();
         assert_eq!(data, 200000i32.to_le_bytes().to_vec());
-        let data = Position::write_with_resolution(2.0, Resolution::Float)
-            .unwrap()
-            .data;
+        let data = Position::encode(2.0, Resolution::Float).unwrap();
         assert_eq!(data, 2.0f32.to_le_bytes().to_vec());
 
-        let position = Position::write(-2.0);
-        let data = position.unwrap().data;
+        let data = Position::encode(-2.0, Resolution::Float).unwrap();
         assert_eq!(data, vec![0, 0, 0, 192]);
         let from_data = Position::from_bytes(&data, Resolution::Float).unwrap();
         assert_eq!(from_data, -2.0);
 
-        let data = Position::write_with_resolution(-2.0, Resolution::Int8);
+        let data = Position::encode(-2.0, Resolution::Int8);
         assert!(data.is_err()); // OVERFLOW
-        let data = Position::write_with_resolution(-2.0, Resolution::Int16)
-            .unwrap()
-            .data;
+        let data = Position::encode(-2.0, Resolution::Int16).unwrap();
         assert_eq!(data, (-20000i16).to_le_bytes().to_vec());
-        let data = Position::write_with_resolution(-2.0, Resolution::Int32)
-            .unwrap()
-            .data;
+        let data = Position::encode(-2.0, Resolution::Int32).unwrap();
         assert_eq!(data, (-200000i32).to_le_bytes().to_vec());
-        let data = Position::write_with_resolution(-2.0, Resolution::Float)
-            .unwrap()
-            .data;
+        let data = Position::encode(-2.0, Resolution::Float).unwrap();
         assert_eq!(data, (-2.0f32).to_le_bytes().to_vec());
     }
 
@@ -1108,60 +1889,194 @@ mod tests {
 
     #[test]
     fn test_i32_register() {
-        let data = MillisecondCounter::write_with_resolution(1, Resolution::Int8)
-            .unwrap()
-            .data;
+        let data = MillisecondCounter::encode(1, Resolution::Int8).unwrap();
         assert_eq!(data, vec!(1));
-        let data = MillisecondCounter::write_with_resolution(1, Resolution::Int16)
-            .unwrap()
-            .data;
+        let data = MillisecondCounter::encode(1, Resolution::Int16).unwrap();
         assert_eq!(data, vec!(1, 0));
-        let data = MillisecondCounter::write_with_resolution(1, Resolution::Int32)
-            .unwrap()
-            .data;
+        let data = MillisecondCounter::encode(1, Resolution::Int32).unwrap();
         assert_eq!(data, vec!(1, 0, 0, 0));
-        let data = MillisecondCounter::write_with_resolution(1, Resolution::Float);
+        let data = MillisecondCounter::encode(1, Resolution::Float);
         assert!(data.is_err());
 
-        let data = MillisecondCounter::write_with_resolution(200, Resolution::Int8);
+        let data = MillisecondCounter::encode(200, Resolution::Int8);
         assert!(data.is_err());
     }
 
     #[test]
     fn test_f32_nan() {
-        let data = Position::write_with_resolution(f32::NAN, Resolution::Float)
-            .unwrap()
-            .data;
+        let data = Position::encode(f32::NAN, Resolution::Float).unwrap();
         assert_eq!(data, vec!(0, 0, 192, 127));
         assert!(Position::from_bytes(&data, Resolution::Float)
             .unwrap()
             .is_nan());
 
-        let data = Position::write_with_resolution(f32::NAN, Resolution::Int8)
-            .unwrap()
-            .data;
+        let data = Position::encode(f32::NAN, Resolution::Int8).unwrap();
         assert_eq!(data, vec!(i8::MIN as u8));
         assert!(Position::from_bytes(&data, Resolution::Int8)
             .unwrap()
             .is_nan());
 
-        let data = Position::write_with_resolution(f32::NAN, Resolution::Int16)
-            .unwrap()
-            .data;
+        let data = Position::encode(f32::NAN, Resolution::Int16).unwrap();
         assert_eq!(data, vec!(0, 128));
         assert!(Position::from_bytes(&data, Resolution::Int16)
             .unwrap()
             .is_nan());
 
-        let data = Position::write_with_resolution(f32::NAN, Resolution::Int32)
-            .unwrap()
-            .data;
+        let data = Position::encode(f32::NAN, Resolution::Int32).unwrap();
         assert_eq!(data, vec!(0, 0, 0, 128));
         assert!(Position::from_bytes(&data, Resolution::Int32)
             .unwrap()
             .is_nan());
     }
 
+    #[test]
+    fn test_register_data_new() {
+        let data = RegisterData::new(RegisterAddr::Position, Resolution::Int16, Some(vec![0, 1]));
+        assert_eq!(data.unwrap().as_res::<Position>().unwrap().value(), 0.0256);
+        assert!(RegisterData::new(RegisterAddr::Position, Resolution::Float, None).is_ok());
+        assert!(matches!(
+            RegisterData::new(RegisterAddr::Position, Resolution::Int16, Some(vec![0])),
+            Err(RegisterError::InvalidData)
+        ));
+        assert!(matches!(
+            RegisterData::new(RegisterAddr::Mode, Resolution::Int8, Some(vec![0, 0])),
+            Err(RegisterError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn test_radians() {
+        let position = CommandPosition::from_radians(std::f32::consts::PI).unwrap();
+        assert_eq!(position.data, CommandPosition::write(0.5).unwrap().data);
+        let position = CommandStopPosition::from_radians(-TAU).unwrap();
+        assert_eq!(
+            position.data,
+            CommandStopPosition::write(-1.0).unwrap().data
+        );
+        let velocity = CommandVelocity::from_rad_per_sec(2.0 * TAU).unwrap();
+        assert_eq!(velocity.data, CommandVelocity::write(2.0).unwrap().data);
+        let limit = VelocityLimit::from_rad_per_sec(TAU).unwrap();
+        assert_eq!(limit.data, VelocityLimit::write(1.0).unwrap().data);
+    }
+
+    #[test]
+    fn test_command_register() {
+        assert_eq!(
+            RegisterAddr::Position.command_register(),
+            Some(RegisterAddr::CommandPosition)
+        );
+        assert_eq!(
+            RegisterAddr::Mode.command_register(),
+            Some(RegisterAddr::Mode)
+        );
+        assert_eq!(RegisterAddr::Fault.command_register(), None);
+        for addr in RegisterAddr::ALL {
+            if let Some(command) = addr.command_register() {
+                assert!(command.is_writeable());
+                assert_eq!(command.mapping(), addr.mapping());
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_table() {
+        for addr in RegisterAddr::ALL {
+            assert_eq!(RegisterAddr::from_name(addr.name()), Some(*addr));
+        }
+        assert_eq!(RegisterAddr::FixedVoltageOverride.name(), "FixedVoltage");
+
+        let data = RegisterAddr::CommandPosition
+            .write_f64(2.0, Resolution::Int16)
+            .unwrap();
+        assert_eq!(data.data, Some(20000i16.to_le_bytes().to_vec()));
+        assert_eq!(RegisterAddr::CommandPosition.read_f64(&data).unwrap(), 2.0);
+
+        let data = RegisterAddr::Mode
+            .write_f64(10.0, Resolution::Int8)
+            .unwrap();
+        assert_eq!(data.data, Some(vec![10]));
+        assert!(RegisterAddr::Mode
+            .write_f64(100.0, Resolution::Int8)
+            .is_err());
+    }
+
+    #[test]
+    fn test_register_kinds() {
+        fn command<R: CommandRegister>() -> RegisterAddr {
+            R::address()
+        }
+        fn status<R: StatusRegister>() -> RegisterAddr {
+            R::address()
+        }
+        assert_eq!(command::<Mode>(), RegisterAddr::Mode);
+        assert_eq!(command::<CommandPosition>(), RegisterAddr::CommandPosition);
+        assert_eq!(status::<Position>(), RegisterAddr::Position);
+        assert_eq!(status::<Fault>(), RegisterAddr::Fault);
+
+        // status registers can still be encoded by address, as in a description
+        let data = RegisterAddr::Position
+            .write_f64(0.5, Resolution::Float)
+            .unwrap();
+        assert_eq!(RegisterAddr::Position.read_f64(&data).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn test_command_timeout() {
+        let read = |timeout: Write<CommandTimeout>| {
+            RegisterData::from(timeout)
+                .as_res::<CommandTimeout>()
+                .unwrap()
+                .as_duration()
+        };
+        let timeout = CommandTimeout::from_duration(Duration::from_millis(250)).unwrap();
+        assert_eq!(read(timeout), Some(Duration::from_millis(250)));
+        assert_eq!(read(CommandTimeout::write(f32::NAN).unwrap()), None);
+        // steps of 10ms at Int8
+        let timeout = CommandTimeout::write_with_resolution(0.5, Resolution::Int8).unwrap();
+        assert_eq!(timeout.data, vec![50]);
+    }
+
+    #[test]
+    fn test_fault_recoverable() {
+        assert!(Faults::TimingViolation.is_recoverable());
+        assert!(Faults::StopPositionDeprecated.is_recoverable());
+        assert!(!Faults::MotorDriverFault.is_recoverable());
+        assert!(!Faults::OverTemperature.is_recoverable());
+    }
+
+    #[test]
+    fn test_unsigned_write() {
+        let data = SerialNumber::encode(100, Resolution::Int8).unwrap();
+        assert_eq!(data, vec![100]);
+        assert!(matches!(
+            SerialNumber::encode(200, Resolution::Int8),
+            Err(RegisterError::ResolutionTooSmall {
+                register: "SerialNumber",
+                resolution: Resolution::Int8,
+                required: Resolution::Int16,
+            })
+        ));
+        let data = SerialNumber::encode(30000, Resolution::Int16).unwrap();
+        assert_eq!(data, 30000i16.to_le_bytes().to_vec());
+        assert!(matches!(
+            SerialNumber::encode(40000, Resolution::Int16),
+            Err(RegisterError::ResolutionTooSmall {
+                required: Resolution::Int32,
+                ..
+            })
+        ));
+        let data = SerialNumber::encode(u32::MAX, Resolution::Int32).unwrap();
+        assert_eq!(data, vec![0xff; 4]);
+
+        assert!(matches!(
+            RegisterAddr::SerialNumber.write_f64(-1.0, Resolution::Int32),
+            Err(RegisterError::NegativeValue)
+        ));
+        assert!(RegisterAddr::SerialNumber
+            .write_f64(1.0, Resolution::Int32)
+            .is_ok());
+    }
+
     #[test]
     fn get_data_from_bytes() {
         let reg = RegisterData {
@@ -0,0 +1,137 @@
+//! Detecting controller resets from the [`MillisecondCounter`](crate::registers::MillisecondCounter).
+
+use crate::registers;
+use crate::ResponseFrame;
+use std::time::{Duration, Instant};
+
+/// The fraction of the host time that passed by which the counter may lag on top of the tolerance
+const MAX_DRIFT: f64 = 0.02;
+
+/// Detects resets of a controller, such as from a brown-out, by following its
+/// [`registers::MillisecondCounter`] across queries.
+///
+/// The counter counts milliseconds since the controller started and wraps from `i32::MAX` to `i32::MIN`
+/// after about 24.8 days. A reading is compared with the previous one using the wrapping difference,
+/// so a wrap is seen as the small step forward it is. A reset is detected when the counter steps backward,
+/// or advances less than the time that passed on the host minus the allowed lag, as the counter restarted
+/// from zero in between the readings. The allowed lag is the tolerance plus 2% of the time that passed,
+/// so a counter running slightly slow relative to the host clock is not seen as a reset over a long gap.
+///
+/// Once detected the reset is remembered until [`ResetDetector::clear`] is called, as any homing or zeroing
+/// done before it is no longer valid.
+///
+/// ```rust
+/// # use moteus::ResetDetector;
+/// # use std::time::{Duration, Instant};
+/// let mut detector = ResetDetector::new(Duration::from_millis(50));
+/// let start = Instant::now();
+/// assert!(!detector.update_at(120_000, start));
+/// assert!(!detector.update_at(121_000, start + Duration::from_secs(1)));
+/// // the controller restarted 200ms ago
+/// assert!(detector.update_at(200, start + Duration::from_secs(2)));
+/// assert!(detector.reset_detected());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResetDetector {
+    tolerance: Duration,
+    last: Option<(i32, Instant)>,
+    reset: bool,
+}
+
+impl ResetDetector {
+    /// Creates a [`ResetDetector`] allowing the counter to lag the host clock by up to `tolerance`,
+    /// such as from the latency of the queries, plus the drift between the clocks.
+    pub fn new(tolerance: Duration) -> Self {
+        Self {
+            tolerance,
+            last: None,
+            reset: false,
+        }
+    }
+
+    /// Records a reading of the counter received now, returning `true` if a reset was detected since the last reading
+    pub fn update(&mut self, counter: i32) -> bool {
+        self.update_at(counter, Instant::now())
+    }
+
+    /// Records a reading of the counter received at `now`, returning `true` if a reset was detected since the last reading
+    pub fn update_at(&mut self, counter: i32, now: Instant) -> bool {
+        let reset = self.last.is_some_and(|(last, time)| {
+            let advanced = counter.wrapping_sub(last);
+            let elapsed = now.saturating_duration_since(time);
+            let allowed = self.tolerance + elapsed.mul_f64(MAX_DRIFT);
+            advanced < 0 || Duration::from_millis(advanced as u64) + allowed < elapsed
+        });
+        self.last = Some((counter, now));
+        self.reset |= reset;
+        reset
+    }
+
+    /// Records the [`registers::MillisecondCounter`] of a response received now, returning `true` if a reset was detected.
+    /// Responses without the counter are ignored.
+    pub fn update_from(&mut self, response: &ResponseFrame) -> bool {
+        response
+            .get::<registers::MillisecondCounter>()
+            .is_some_and(|counter| self.update(counter.value()))
+    }
+
+    /// Returns `true` if a reset has been detected since the detector was created or cleared
+    pub fn reset_detected(&self) -> bool {
+        self.reset
+    }
+
+    /// Clears a detected reset, such as after homing the controller again
+    pub fn clear(&mut self) {
+        self.reset = false;
+    }
+
+    /// Returns the time since the controller started, from the last reading of the counter.
+    /// This is only the uptime until the counter first wraps.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.last
+            .and_then(|(counter, _)| u64::try_from(counter).ok())
+            .map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let mut detector = ResetDetector::new(Duration::from_millis(20));
+        let start = Instant::now();
+        assert!(!detector.update_at(i32::MAX - 5, start));
+        assert!(!detector.update_at(i32::MIN + 4, start + Duration::from_millis(10)));
+        assert!(!detector.reset_detected());
+        assert_eq!(detector.uptime(), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut detector = ResetDetector::new(Duration::from_millis(20));
+        let start = Instant::now();
+        assert!(!detector.update_at(5_000, start));
+        // reset while the counter still reads higher than before
+        assert!(detector.update_at(6_000, start + Duration::from_secs(10)));
+        assert!(!detector.update_at(6_010, start + Duration::from_millis(10_015)));
+        assert!(detector.reset_detected());
+        detector.clear();
+        assert!(!detector.reset_detected());
+        assert_eq!(detector.uptime(), Some(Duration::from_millis(6_010)));
+
+        assert!(detector.update_at(10, start + Duration::from_millis(10_020)));
+    }
+
+    #[test]
+    fn test_drift() {
+        let mut detector = ResetDetector::new(Duration::from_millis(20));
+        let start = Instant::now();
+        assert!(!detector.update_at(5_000, start));
+        // the counter runs 0.5% slow over a long gap between queries
+        assert!(!detector.update_at(14_950, start + Duration::from_secs(10)));
+        assert!(!detector.update_at(3_596_950, start + Duration::from_secs(3_610)));
+        assert!(!detector.reset_detected());
+    }
+}
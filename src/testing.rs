@@ -0,0 +1,62 @@
+//! Internal frame types and helpers for writing protocol conformance tests, enabled with the `testing` feature.
+//!
+//! These give access to the exact bytes sent and the parsing of replies, so the output of this crate can be
+//! compared against other moteus libraries. This module is not covered by semver and may change in any release.
+
+use crate::protocol::registers::CustomRegisters;
+pub use crate::protocol::SubFrame;
+use crate::{Frame, FrameError, FrameParseError, ResponseFrame};
+
+/// Returns the bytes of a [`Frame`], as sent to the controller.
+pub fn frame_to_bytes(frame: &Frame) -> Result<Vec<u8>, FrameError> {
+    frame.as_bytes()
+}
+
+/// Returns the [`SubFrame`]s of a [`Frame`]
+pub fn subframes(frame: &Frame) -> &[SubFrame] {
+    frame.subframes()
+}
+
+/// Returns the bytes of a [`SubFrame`]
+pub fn subframe_to_bytes(subframe: &SubFrame) -> Result<Vec<u8>, FrameError> {
+    subframe.as_bytes()
+}
+
+/// Parses a single [`SubFrame`] from the start of `buf`, returning it and the number of bytes consumed.
+/// A NOP register is consumed without returning a [`SubFrame`].
+pub fn subframe_from_bytes(buf: &[u8]) -> Result<(Option<SubFrame>, usize), FrameParseError> {
+    SubFrame::from_bytes_with(buf, &CustomRegisters::default())
+}
+
+/// Parses a [`ResponseFrame`] from bytes
+pub fn response_from_bytes(buf: &[u8]) -> Result<ResponseFrame, FrameParseError> {
+    ResponseFrame::from_bytes_with(buf, &CustomRegisters::default())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::registers::{FrameRegisters, Mode, Modes, Writeable};
+
+    #[test]
+    fn test_round_trip() {
+        let frame = Frame::with_builder(|b| {
+            b.add(Mode::write(Modes::Stopped).unwrap());
+        })
+        .build();
+        let bytes = frame_to_bytes(&frame).unwrap();
+        assert_eq!(bytes, vec![0x01, 0x00, 0x00]);
+        assert_eq!(subframes(&frame).len(), 1);
+        assert_eq!(subframe_to_bytes(&subframes(&frame)[0]).unwrap(), bytes);
+
+        let (subframe, len) = subframe_from_bytes(&[0x21, 0x00, 0x00, 0x50]).unwrap();
+        let subframe = subframe.unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(subframe.register(), FrameRegisters::ReplyInt8);
+        assert_eq!(subframe.registers().len(), 1);
+        assert!(!response_from_bytes(&[0x21, 0x00, 0x00, 0x50])
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Limiting how often commands are sent to each controller.
+
+use crate::FrameBuilder;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// What a [`Throttle`] does with a command sent before the interval since the previous command has passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottlePolicy {
+    /// The command is not sent, and [`crate::Error::Throttled`] is returned
+    Drop,
+    /// The command is sent once the interval has passed, sleeping the calling thread until then
+    Block,
+    /// The command is held as the pending command of the controller, replacing any older pending command,
+    /// and [`crate::Error::Throttled`] is returned. Pending commands are sent by [`crate::Controller::flush_throttled`].
+    Coalesce,
+}
+
+/// Enforces a minimum interval between commands to each controller, to avoid saturating a slow bus.
+///
+/// Only frames which write the [`crate::registers::Mode`] are throttled, queries and [`crate::frame::Stop`]
+/// are always sent.
+/// Enable it on a [`crate::Controller`] with [`crate::Controller::set_throttle`].
+///
+/// ```rust
+/// # use moteus::{Throttle, ThrottlePolicy};
+/// # use std::time::{Duration, Instant};
+/// let mut throttle = Throttle::new(Duration::from_millis(10), ThrottlePolicy::Drop);
+/// let start = Instant::now();
+/// assert_eq!(throttle.remaining_at(1, start), None);
+/// throttle.sent_at(1, start);
+/// assert_eq!(throttle.remaining_at(1, start + Duration::from_millis(4)), Some(Duration::from_millis(6)));
+/// assert_eq!(throttle.remaining_at(1, start + Duration::from_millis(10)), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    interval: Duration,
+    policy: ThrottlePolicy,
+    last: HashMap<u8, Instant>,
+    pending: HashMap<u8, FrameBuilder>,
+}
+
+impl Throttle {
+    /// Creates a [`Throttle`] allowing one command to each controller per `interval`
+    pub fn new(interval: Duration, policy: ThrottlePolicy) -> Self {
+        Self {
+            interval,
+            policy,
+            last: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The minimum interval between commands to a controller
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The [`ThrottlePolicy`] applied to commands sent too soon
+    pub fn policy(&self) -> ThrottlePolicy {
+        self.policy
+    }
+
+    /// Records that a command was sent to `id` at `time`, discarding any pending command
+    pub fn sent_at(&mut self, id: u8, time: Instant) {
+        let _ = self.last.insert(id, time);
+        let _ = self.pending.remove(&id);
+    }
+
+    /// Returns the time until `id` can be commanded again, or [`None`] if it can be commanded at `now`
+    pub fn remaining_at(&self, id: u8, now: Instant) -> Option<Duration> {
+        let last = self.last.get(&id)?;
+        self.interval
+            .checked_sub(now.saturating_duration_since(*last))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Returns the ids with a pending command held by [`ThrottlePolicy::Coalesce`]
+    pub fn pending(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pending.keys().copied()
+    }
+
+    /// Holds `command` as the pending command of `id`
+    pub(crate) fn hold(&mut self, id: u8, command: FrameBuilder) {
+        let _ = self.pending.insert(id, command);
+    }
+
+    /// Forgets `id`, discarding its pending command and the time of its last command
+    pub(crate) fn forget(&mut self, id: u8) {
+        let _ = self.last.remove(&id);
+        let _ = self.pending.remove(&id);
+    }
+
+    /// Returns the pending command of `id`, if any
+    pub(crate) fn pending_command(&self, id: u8) -> Option<&FrameBuilder> {
+        self.pending.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Stop;
+
+    #[test]
+    fn test_remaining() {
+        let mut throttle = Throttle::new(Duration::from_millis(10), ThrottlePolicy::Coalesce);
+        let start = Instant::now();
+        throttle.sent_at(1, start);
+        assert_eq!(
+            throttle.remaining_at(1, start + Duration::from_millis(3)),
+            Some(Duration::from_millis(7))
+        );
+        assert_eq!(throttle.remaining_at(2, start), None);
+
+        throttle.hold(1, Stop.into());
+        assert_eq!(throttle.pending().collect::<Vec<_>>(), vec![1]);
+        throttle.sent_at(1, start + Duration::from_millis(10));
+        assert_eq!(throttle.pending().count(), 0);
+    }
+}
@@ -10,9 +10,13 @@ impl Transport for FdCanUSB<fdcanusb::serial2::SerialPort> {
         self.write(frame).map_err(Error::Transport)
     }
 
+    /// Reads a frame, returning [`Error::NoResponse`] if none arrives before the read times out
     fn receive(&mut self) -> Result<Self::Frame, Error<Self::Error>> {
-        self.read()
-            .map_err(fdcanusb::TransferError::Read)
-            .map_err(Error::Transport)
+        self.read().map_err(|e| match e {
+            fdcanusb::ReadError::Io(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                Error::NoResponse
+            }
+            e => Error::Transport(fdcanusb::TransferError::Read(e)),
+        })
     }
 }
@@ -0,0 +1,73 @@
+//! Classifying the bus voltage of a controller, read with [`crate::Controller::bus_voltage`].
+
+use crate::registers::Faults;
+
+/// The state of the bus voltage relative to a set of [`VoltageThresholds`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoltageStatus {
+    /// The voltage is below the under-voltage threshold
+    UnderVoltage,
+    /// The voltage is between the thresholds
+    Normal,
+    /// The voltage is above the over-voltage threshold
+    OverVoltage,
+}
+
+impl VoltageStatus {
+    /// Returns the fault the moteus raises in this state, [`Faults::UnderVoltage`] or [`Faults::OverVoltage`],
+    /// or [`None`] if the voltage is normal.
+    pub fn fault(&self) -> Option<Faults> {
+        match self {
+            VoltageStatus::UnderVoltage => Some(Faults::UnderVoltage),
+            VoltageStatus::Normal => None,
+            VoltageStatus::OverVoltage => Some(Faults::OverVoltage),
+        }
+    }
+}
+
+/// The bounds of the bus voltage in volts, used to classify a reading into a [`VoltageStatus`].
+///
+/// To warn before the moteus faults, set these inside the limits configured on the controller,
+/// such as `servo.max_voltage`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoltageThresholds {
+    /// Readings below this are [`VoltageStatus::UnderVoltage`]
+    pub under: f32,
+    /// Readings above this are [`VoltageStatus::OverVoltage`]
+    pub over: f32,
+}
+
+impl VoltageThresholds {
+    /// Classifies a bus voltage in volts
+    pub fn classify(&self, voltage: f32) -> VoltageStatus {
+        if voltage < self.under {
+            VoltageStatus::UnderVoltage
+        } else if voltage > self.over {
+            VoltageStatus::OverVoltage
+        } else {
+            VoltageStatus::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let thresholds = VoltageThresholds {
+            under: 20.0,
+            over: 48.0,
+        };
+        assert_eq!(thresholds.classify(19.5), VoltageStatus::UnderVoltage);
+        assert_eq!(thresholds.classify(24.0), VoltageStatus::Normal);
+        assert_eq!(thresholds.classify(48.0), VoltageStatus::Normal);
+        assert_eq!(thresholds.classify(50.0), VoltageStatus::OverVoltage);
+        assert_eq!(
+            VoltageStatus::UnderVoltage.fault(),
+            Some(Faults::UnderVoltage)
+        );
+        assert_eq!(VoltageStatus::Normal.fault(), None);
+    }
+}
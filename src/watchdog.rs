@@ -0,0 +1,128 @@
+//! Host side tracking of the time since each controller was last commanded.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The default watchdog timeout of the moteus, `servo.default_timeout_s`
+pub const WATCHDOG_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How close a [`WatchdogGuard`] is to the watchdog timeout of a controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogStatus {
+    /// No command has been sent since the guard was created or the controller was stopped
+    Idle,
+    /// The next command can safely wait for the remaining time
+    Safe(Duration),
+    /// Less than the margin of the guard remains, the next command should be sent now
+    Near(Duration),
+    /// The timeout has passed, so the controller has likely entered [`crate::registers::Modes::Timeout`]
+    Expired,
+}
+
+/// Tracks the time since the last command to each controller, to warn before the watchdog of the moteus trips.
+///
+/// This is advisory, commands are not sent for you. Enable it on a [`crate::Controller`] with
+/// [`crate::Controller::set_watchdog_guard`] to record each command automatically,
+/// or call [`WatchdogGuard::commanded`] yourself.
+///
+/// ```rust
+/// # use moteus::{WatchdogGuard, WatchdogStatus};
+/// # use std::time::{Duration, Instant};
+/// let mut guard = WatchdogGuard::new(Duration::from_millis(20));
+/// let start = Instant::now();
+/// guard.commanded_at(1, start);
+/// assert!(matches!(guard.status_at(1, start + Duration::from_millis(50)), WatchdogStatus::Safe(_)));
+/// assert!(matches!(guard.status_at(1, start + Duration::from_millis(90)), WatchdogStatus::Near(_)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchdogGuard {
+    timeout: Duration,
+    margin: Duration,
+    last: HashMap<u8, Instant>,
+}
+
+impl WatchdogGuard {
+    /// Creates a [`WatchdogGuard`] for the default [`WATCHDOG_TIMEOUT`],
+    /// reporting [`WatchdogStatus::Near`] once less than `margin` remains.
+    pub fn new(margin: Duration) -> Self {
+        Self::with_timeout(WATCHDOG_TIMEOUT, margin)
+    }
+
+    /// Creates a [`WatchdogGuard`] for controllers configured with a different watchdog timeout
+    pub fn with_timeout(timeout: Duration, margin: Duration) -> Self {
+        Self {
+            timeout,
+            margin,
+            last: HashMap::new(),
+        }
+    }
+
+    /// The watchdog timeout of the controllers
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Records that a command was sent to `id` now
+    pub fn commanded(&mut self, id: u8) {
+        self.commanded_at(id, Instant::now());
+    }
+
+    /// Records that a command was sent to `id` at `time`
+    pub fn commanded_at(&mut self, id: u8, time: Instant) {
+        let _ = self.last.insert(id, time);
+    }
+
+    /// Forgets `id`, such as after it was sent a [`crate::frame::Stop`], which the watchdog does not apply to
+    pub fn stopped(&mut self, id: u8) {
+        let _ = self.last.remove(&id);
+    }
+
+    /// Returns the [`WatchdogStatus`] of `id` now
+    pub fn status(&self, id: u8) -> WatchdogStatus {
+        self.status_at(id, Instant::now())
+    }
+
+    /// Returns the [`WatchdogStatus`] of `id` at `now`
+    pub fn status_at(&self, id: u8, now: Instant) -> WatchdogStatus {
+        let Some(last) = self.last.get(&id) else {
+            return WatchdogStatus::Idle;
+        };
+        match self
+            .timeout
+            .checked_sub(now.saturating_duration_since(*last))
+        {
+            Some(remaining) if remaining > self.margin => WatchdogStatus::Safe(remaining),
+            Some(remaining) if !remaining.is_zero() => WatchdogStatus::Near(remaining),
+            _ => WatchdogStatus::Expired,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        let mut guard = WatchdogGuard::new(Duration::from_millis(20));
+        let start = Instant::now();
+        assert_eq!(guard.status_at(1, start), WatchdogStatus::Idle);
+
+        guard.commanded_at(1, start);
+        assert_eq!(
+            guard.status_at(1, start + Duration::from_millis(30)),
+            WatchdogStatus::Safe(Duration::from_millis(70))
+        );
+        assert_eq!(
+            guard.status_at(1, start + Duration::from_millis(85)),
+            WatchdogStatus::Near(Duration::from_millis(15))
+        );
+        assert_eq!(
+            guard.status_at(1, start + Duration::from_millis(100)),
+            WatchdogStatus::Expired
+        );
+
+        guard.stopped(1);
+        assert_eq!(guard.status_at(1, start), WatchdogStatus::Idle);
+    }
+}